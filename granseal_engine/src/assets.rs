@@ -0,0 +1,332 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use image::{DynamicImage, ImageFormat, ImageResult};
+
+// A file inside a pack.
+#[derive(Debug)]
+struct Entry {
+    // where its local header starts
+    offset: u64,
+    compressed: u64,
+    size: u64,
+    deflated: bool,
+}
+
+// A zip file mounted with Assets::mount. Only stored and deflated entries are read, not zip64.
+#[derive(Debug)]
+struct Pack {
+    path: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn invalid(path: &Path, why: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?} {}", path, why))
+}
+
+impl Pack {
+    const END: u32 = 0x0605_4b50;
+    const CENTRAL: u32 = 0x0201_4b50;
+    const LOCAL: u32 = 0x0403_4b50;
+
+    // Reads the central directory, entries are only read when asked for.
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        // the end record is the last thing in the file, followed by a comment of up to 64k
+        let tail = len.min(22 + 0xffff);
+        let mut bytes = vec![0; tail as usize];
+        file.seek(SeekFrom::Start(len - tail))?;
+        file.read_exact(&mut bytes)?;
+        let end = (0..bytes.len().saturating_sub(21)).rev()
+            .find(|at| u32_at(&bytes, *at) == Pack::END)
+            .ok_or_else(|| invalid(path, "isn't a zip file"))?;
+        let count = u16_at(&bytes, end + 10) as usize;
+        let directory_size = u32_at(&bytes, end + 12);
+        let directory_offset = u32_at(&bytes, end + 16);
+        if directory_offset == u32::MAX {
+            return Err(invalid(path, "is a zip64 file"));
+        }
+        if directory_offset as u64 + directory_size as u64 > len {
+            return Err(invalid(path, "has a central directory past its end"));
+        }
+
+        let mut directory = vec![0; directory_size as usize];
+        file.seek(SeekFrom::Start(directory_offset as u64))?;
+        file.read_exact(&mut directory)?;
+        let mut entries = HashMap::new();
+        let mut at = 0;
+        for _ in 0..count {
+            if at + 46 > directory.len() || u32_at(&directory, at) != Pack::CENTRAL {
+                return Err(invalid(path, "has a broken central directory"));
+            }
+            let method = u16_at(&directory, at + 10);
+            let name_len = u16_at(&directory, at + 28) as usize;
+            let extra_len = u16_at(&directory, at + 30) as usize;
+            let comment_len = u16_at(&directory, at + 32) as usize;
+            let name_end = (at + 46 + name_len).min(directory.len());
+            let name = String::from_utf8_lossy(&directory[at + 46..name_end]).to_string();
+            if !name.ends_with('/') && (method == 0 || method == 8) {
+                entries.insert(name, Entry {
+                    offset: u32_at(&directory, at + 42) as u64,
+                    compressed: u32_at(&directory, at + 20) as u64,
+                    size: u32_at(&directory, at + 24) as u64,
+                    deflated: method == 8,
+                });
+            }
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Self { path: path.to_path_buf(), entries })
+    }
+    fn read(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let entry = self.entries.get(name)?;
+        Some(self.read_entry(entry))
+    }
+    fn read_entry(&self, entry: &Entry) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        let mut header = [0; 30];
+        file.seek(SeekFrom::Start(entry.offset))?;
+        file.read_exact(&mut header)?;
+        if u32_at(&header, 0) != Pack::LOCAL {
+            return Err(invalid(&self.path, "has a broken entry"));
+        }
+        // the local header has its own name and extra field lengths
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        file.seek(SeekFrom::Current(skip))?;
+        let mut data = vec![0; entry.compressed as usize];
+        file.read_exact(&mut data)?;
+        if !entry.deflated {
+            return Ok(data);
+        }
+        miniz_oxide::inflate::decompress_to_vec_with_limit(&data, entry.size as usize)
+            .map_err(|e| invalid(&self.path, &format!("has an entry that doesn't inflate: {:?}", e.status)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sources {
+    paths: Vec<PathBuf>,
+    packs: Vec<Pack>,
+    embedded: HashMap<String, &'static [u8]>,
+}
+
+// Where images and data files are read from: the search paths first, then mounted packs, the last
+// mounted first, then files embedded in the executable. Clones share their sources, so the loader
+// and watcher threads see sources added later.
+#[derive(Clone, Debug)]
+pub struct Assets {
+    sources: Arc<RwLock<Sources>>,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Names are relative, with forward slashes, like the files inside a pack. Every lookup goes through
+// this, on disk, in packs and embedded.
+fn normalize(name: &str) -> String {
+    let name = name.replace('\\', "/");
+    let mut name = name.as_str();
+    while let Some(rest) = name.strip_prefix("./") {
+        name = rest;
+    }
+    name.to_string()
+}
+
+impl Assets {
+    // Searches the current directory, then the directory of the executable.
+    pub fn new() -> Self {
+        let mut paths = vec![PathBuf::from(".")];
+        if let Some(dir) = Assets::executable_dir() {
+            paths.push(dir);
+        }
+        Self { sources: Arc::new(RwLock::new(Sources { paths, ..Sources::default() })) }
+    }
+    fn executable_dir() -> Option<PathBuf> {
+        std::env::current_exe().ok()?.parent().map(Path::to_path_buf)
+    }
+    // Adds a directory to search, relative paths are relative to the executable, not the current directory.
+    pub fn search_path<P: AsRef<Path>>(&self, dir: P) -> &Self {
+        let dir = dir.as_ref();
+        let dir = match Assets::executable_dir() {
+            Some(exe) if dir.is_relative() => exe.join(dir),
+            _ => dir.to_path_buf(),
+        };
+        self.sources.write().unwrap().paths.push(dir);
+        self
+    }
+    pub fn clear_search_paths(&self) -> &Self {
+        self.sources.write().unwrap().paths.clear();
+        self
+    }
+    // Serves bytes as a file, usually from include_bytes!.
+    pub fn embed(&self, name: &str, bytes: &'static [u8]) -> &Self {
+        self.sources.write().unwrap().embedded.insert(normalize(name), bytes);
+        self
+    }
+    // Serves the files inside a zip file, which is found on the search paths.
+    pub fn mount<P: AsRef<Path>>(&self, pack: P) -> io::Result<&Self> {
+        let path = self.locate(pack.as_ref())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("pack not found: {:?}", pack.as_ref())))?;
+        let pack = Pack::open(&path)?;
+        self.sources.write().unwrap().packs.push(pack);
+        Ok(self)
+    }
+    // The file on disk for a name, absolute paths are used as they are.
+    pub fn locate<P: AsRef<Path>>(&self, name: P) -> Option<PathBuf> {
+        let name = name.as_ref();
+        if name.is_absolute() {
+            return name.is_file().then(|| name.to_path_buf());
+        }
+        let name = normalize(&name.to_string_lossy());
+        self.sources.read().unwrap().paths.iter()
+            .map(|dir| dir.join(&name))
+            .find(|path| path.is_file())
+    }
+    pub fn exists(&self, name: &str) -> bool {
+        if self.locate(name).is_some() {
+            return true;
+        }
+        let name = normalize(name);
+        let sources = self.sources.read().unwrap();
+        sources.packs.iter().any(|pack| pack.entries.contains_key(&name)) || sources.embedded.contains_key(&name)
+    }
+    pub fn read(&self, name: &str) -> io::Result<Cow<'static, [u8]>> {
+        if let Some(path) = self.locate(name) {
+            return std::fs::read(path).map(Cow::Owned);
+        }
+        let normalized = normalize(name);
+        let sources = self.sources.read().unwrap();
+        if let Some(bytes) = sources.packs.iter().rev().find_map(|pack| pack.read(&normalized)) {
+            return bytes.map(Cow::Owned);
+        }
+        match sources.embedded.get(&normalized) {
+            Some(bytes) => Ok(Cow::Borrowed(*bytes)),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("asset not found: {}", name))),
+        }
+    }
+    pub fn read_to_string(&self, name: &str) -> io::Result<String> {
+        let bytes = self.read(name)?;
+        String::from_utf8(bytes.into_owned()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    // Decodes an image, its format from the extension like image::open, or from its contents.
+    pub fn open_image(&self, name: &str) -> ImageResult<DynamicImage> {
+        let bytes = self.read(name)?;
+        match ImageFormat::from_path(name) {
+            Ok(format) => image::load_from_memory_with_format(&bytes, format),
+            Err(_) => image::load_from_memory(&bytes),
+        }
+    }
+    // When a file on disk last changed, None for packed and embedded files.
+    pub(crate) fn modified(&self, name: &str) -> Option<SystemTime> {
+        std::fs::metadata(self.locate(name)?).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name)
+    }
+
+    fn fixtures() -> Assets {
+        let assets = Assets::new();
+        assets.clear_search_paths().search_path(fixture(""));
+        assets
+    }
+
+    // A broken copy of a fixture, written where Pack::open can read it.
+    fn broken(name: &str, change: impl FnOnce(&mut Vec<u8>)) -> PathBuf {
+        let mut bytes = std::fs::read(fixture("stored.zip")).unwrap();
+        change(&mut bytes);
+        let path = std::env::temp_dir().join(format!("granseal-{}-{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_entries() {
+        for zip in ["stored.zip", "deflated.zip"] {
+            let pack = Pack::open(&fixture(zip)).unwrap();
+            let mut names: Vec<&String> = pack.entries.keys().collect();
+            names.sort();
+            assert_eq!(names, ["hello.txt", "sprites/data.json"]);
+            assert_eq!(pack.entries["sprites/data.json"].deflated, zip == "deflated.zip");
+            assert_eq!(pack.read("hello.txt").unwrap().unwrap(), b"hello from a pack\n");
+            let json = pack.read("sprites/data.json").unwrap().unwrap();
+            assert_eq!(json.len(), 216);
+            assert!(json.starts_with(b"{\"frames\": []}"));
+            assert!(pack.read("missing.txt").is_none());
+        }
+    }
+
+    #[test]
+    fn mounted_names_are_normalized() {
+        let assets = fixtures();
+        assets.mount("./deflated.zip").unwrap();
+        assert_eq!(&*assets.read("hello.txt").unwrap(), b"hello from a pack\n");
+        assert!(assets.exists("./sprites/data.json"));
+        assert!(assets.exists("sprites\\data.json"));
+        assert!(assets.read_to_string(".\\sprites/data.json").unwrap().starts_with("{"));
+        assert!(!assets.exists("sprites"));
+        assert_eq!(assets.read("nope.txt").unwrap_err().kind(), io::ErrorKind::NotFound);
+        // packed files aren't on disk
+        assert_eq!(assets.locate("hello.txt"), None);
+        assert_eq!(assets.modified("hello.txt"), None);
+    }
+
+    #[test]
+    fn files_on_disk_are_normalized_too() {
+        let assets = fixtures();
+        assert_eq!(assets.locate(".\\stored.zip"), Some(fixture("").join("stored.zip")));
+        assert!(assets.modified("./stored.zip").is_some());
+        assert_eq!(assets.locate(fixture("stored.zip")), Some(fixture("stored.zip")));
+    }
+
+    #[test]
+    fn embedded_names_are_normalized() {
+        let assets = fixtures();
+        assets.embed(".\\a\\b.txt", b"embedded");
+        assert_eq!(&*assets.read("a/b.txt").unwrap(), b"embedded");
+        assert!(assets.exists("./a/b.txt"));
+    }
+
+    #[test]
+    fn rejects_broken_packs() {
+        let not_zip = broken("not.zip", |bytes| bytes.iter_mut().for_each(|b| *b = 0));
+        assert_eq!(Pack::open(&not_zip).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        // a directory offset past the end of the file
+        let past_end = broken("past-end.zip", |bytes| {
+            let end = bytes.len() - 22;
+            bytes[end + 16..end + 20].copy_from_slice(&0x7fff_0000u32.to_le_bytes());
+        });
+        assert_eq!(Pack::open(&past_end).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        // a directory claiming more entries than it holds
+        let short = broken("short.zip", |bytes| {
+            let end = bytes.len() - 22;
+            bytes[end + 10] = 9;
+        });
+        assert_eq!(Pack::open(&short).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let truncated = broken("truncated.zip", |bytes| bytes.truncate(10));
+        assert!(Pack::open(&truncated).is_err());
+        for path in [not_zip, past_end, short, truncated] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use image::RgbaImage;
+use crate::shape::Rect;
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct Placement {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+}
+
+// Places rectangles on pages of page_size squared, in shelves from the tallest down. Each rectangle
+// gets padding pixels of room on every side. Returns where each rectangle went,
+// None for ones that don't fit on a page at all, and the used height of every page.
+pub fn pack(sizes: &[(u32, u32)], page_size: u32, padding: u32) -> (Vec<Option<Placement>>, Vec<u32>) {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(sizes[*i].1));
+    let mut placed = vec![None; sizes.len()];
+    let mut heights: Vec<u32> = vec![];
+    // position and height of the shelf being filled on the last page
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+    for i in order {
+        let (w, h) = (sizes[i].0 + padding * 2, sizes[i].1 + padding * 2);
+        if w > page_size || h > page_size {
+            continue;
+        }
+        if x + w > page_size {
+            x = 0;
+            y += shelf;
+            shelf = 0;
+        }
+        if heights.is_empty() || y + h > page_size {
+            heights.push(0);
+            x = 0;
+            y = 0;
+            shelf = 0;
+        }
+        let page = heights.len() - 1;
+        placed[i] = Some(Placement { page, x: x + padding, y: y + padding });
+        x += w;
+        shelf = shelf.max(h);
+        heights[page] = heights[page].max(y + shelf);
+    }
+    (placed, heights)
+}
+
+// Copies an image onto a page at x, y and repeats its edge pixels out into the padding,
+// so filtering at the edges of a region never picks up its neighbours.
+fn blit(page: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, padding: u32) {
+    let (w, h) = image.dimensions();
+    let p = padding as i64;
+    for dy in -p..h as i64 + p {
+        for dx in -p..w as i64 + p {
+            let sx = dx.clamp(0, w as i64 - 1) as u32;
+            let sy = dy.clamp(0, h as i64 - 1) as u32;
+            let (px, py) = ((x as i64 + dx) as u32, (y as i64 + dy) as u32);
+            if px < page.width() && py < page.height() {
+                page.put_pixel(px, py, *image.get_pixel(sx, sy));
+            }
+        }
+    }
+}
+
+// Pages holding the images, and where each image ended up. Images too big for a page are left out.
+pub fn build(images: &[(String, RgbaImage)], page_size: u32, padding: u32) -> (Vec<RgbaImage>, HashMap<String, (usize, Rect)>) {
+    let sizes: Vec<(u32, u32)> = images.iter().map(|(_, image)| image.dimensions()).collect();
+    let (placed, heights) = pack(&sizes, page_size, padding);
+    let mut pages: Vec<RgbaImage> = heights.iter().map(|h| RgbaImage::new(page_size, (*h).max(1))).collect();
+    let mut regions = HashMap::new();
+    for ((name, image), place) in images.iter().zip(placed) {
+        if let Some(Placement { page, x, y }) = place {
+            blit(&mut pages[page], image, x, y, padding);
+            let (w, h) = image.dimensions();
+            regions.insert(name.clone(), (page, Rect::new(x as f32, y as f32, w as f32, h as f32)));
+        }
+    }
+    (pages, regions)
+}
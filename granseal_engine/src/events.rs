@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     KeyEvent {
         state: KeyState,
@@ -20,6 +20,8 @@ pub enum Event {
     Update(Duration),
     Load,
     Resized(u32,u32),
+    // A file watched by Graphics::hot_reload changed, images are already reloaded.
+    Reloaded(String),
 }
 
 
@@ -98,6 +100,11 @@ pub fn map_events(event: &winit::event::WindowEvent) -> Option<Event> {
                 position: [0.0,0.0]
             })
         }
+        winit::event::WindowEvent::CursorMoved { position, .. } => {
+            Some(Event::MouseMoved {
+                position: [position.x,position.y]
+            })
+        }
         _ => None
     }
 }
@@ -0,0 +1,269 @@
+use crate::shape::{Shape, ShapeKind};
+
+// The distance functions of shape_shader.wgsl on the cpu, for asking which shape is under a point.
+// Distances are in pixels from the shape's center, negative inside.
+
+fn length(x: f32, y: f32) -> f32 {
+    (x * x + y * y).sqrt()
+}
+
+pub fn box_dist(p: [f32; 2], r: [f32; 2]) -> f32 {
+    let d = [p[0].abs() - r[0], p[1].abs() - r[1]];
+    length(d[0].max(0.0), d[1].max(0.0)) + d[0].max(d[1]).min(0.0)
+}
+
+pub fn round_box_dist(p: [f32; 2], r: [f32; 2], corner: f32) -> f32 {
+    let radius = corner.clamp(0.0, r[0].min(r[1]).max(0.0));
+    box_dist(p, [r[0] - radius, r[1] - radius]) - radius
+}
+
+pub fn ellipse_dist(p: [f32; 2], radii: [f32; 2]) -> f32 {
+    let q = [p[0].abs(), p[1].abs()];
+    let r = [radii[0].max(0.0001), radii[1].max(0.0001)];
+    let mut t = [std::f32::consts::FRAC_1_SQRT_2; 2];
+    for _ in 0..3 {
+        let e = [
+            (r[0] * r[0] - r[1] * r[1]) / r[0] * t[0].powi(3),
+            (r[1] * r[1] - r[0] * r[0]) / r[1] * t[1].powi(3),
+        ];
+        let rv = [r[0] * t[0] - e[0], r[1] * t[1] - e[1]];
+        let qv = [q[0] - e[0], q[1] - e[1]];
+        let k = length(rv[0], rv[1]) / length(qv[0], qv[1]).max(0.0001);
+        let next = [
+            ((qv[0] * k + e[0]) / r[0]).clamp(0.0, 1.0),
+            ((qv[1] * k + e[1]) / r[1]).clamp(0.0, 1.0),
+        ];
+        // at the center of a circle every point is closest, keep the last guess
+        let l = length(next[0], next[1]);
+        if l > 0.0001 {
+            t = [next[0] / l, next[1] / l];
+        }
+    }
+    let d = length(q[0] - r[0] * t[0], q[1] - r[1] * t[1]);
+    if (q[0] / r[0]).powi(2) + (q[1] / r[1]).powi(2) < 1.0 { -d } else { d }
+}
+
+// Angles go clockwise from +x.
+pub fn wedge_dist(p: [f32; 2], start: f32, end: f32) -> f32 {
+    let half = (end - start) / 2.0;
+    if half >= std::f32::consts::PI {
+        return -1.0e6;
+    }
+    let (sin, cos) = (start + half).sin_cos();
+    let q = [cos * p[0] + sin * p[1], (cos * p[1] - sin * p[0]).abs()];
+    let c = [half.cos(), half.sin()];
+    let along = (q[0] * c[0] + q[1] * c[1]).max(0.0);
+    let m = length(q[0] - c[0] * along, q[1] - c[1] * along);
+    m * (c[0] * q[1] - c[1] * q[0]).signum()
+}
+
+// Pie slice, or ring segment when inner > 0.
+pub fn arc_dist(p: [f32; 2], r: [f32; 2], params: [f32; 4]) -> f32 {
+    let mut d = ellipse_dist(p, r).max(wedge_dist(p, params[0], params[1]));
+    if params[2] > 0.0 {
+        d = d.max(-ellipse_dist(p, [r[0] * params[2], r[1] * params[2]]));
+    }
+    d
+}
+
+// Unsigned distance to connected segments.
+pub fn polyline_dist(p: [f32; 2], points: &[[f32; 2]]) -> f32 {
+    let mut d = f32::MAX;
+    for pair in points.windows(2) {
+        let e = [pair[1][0] - pair[0][0], pair[1][1] - pair[0][1]];
+        let w = [p[0] - pair[0][0], p[1] - pair[0][1]];
+        let t = ((w[0] * e[0] + w[1] * e[1]) / (e[0] * e[0] + e[1] * e[1]).max(0.0001)).clamp(0.0, 1.0);
+        d = d.min(length(w[0] - e[0] * t, w[1] - e[1] * t));
+    }
+    d
+}
+
+// Even-odd, like the shader.
+pub fn polygon_dist(p: [f32; 2], points: &[[f32; 2]]) -> f32 {
+    if points.is_empty() {
+        return f32::MAX;
+    }
+    let mut d = f32::MAX;
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (vi, vj) = (points[i], points[j]);
+        let e = [vj[0] - vi[0], vj[1] - vi[1]];
+        let w = [p[0] - vi[0], p[1] - vi[1]];
+        let t = ((w[0] * e[0] + w[1] * e[1]) / (e[0] * e[0] + e[1] * e[1]).max(0.0001)).clamp(0.0, 1.0);
+        d = d.min(length(w[0] - e[0] * t, w[1] - e[1] * t));
+        let c = [p[1] >= vi[1], p[1] < vj[1], e[0] * w[1] > e[1] * w[0]];
+        if c.iter().all(|c| *c) || !c.iter().any(|c| *c) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    if inside { -d } else { d }
+}
+
+pub fn stroke(d: f32, thickness: f32) -> f32 {
+    (d + thickness / 2.0).abs() - thickness / 2.0
+}
+
+impl Shape {
+    // A point on screen in the shape's own pixels around its center, undoing the transform and angle.
+    pub fn local_point(&self, p: [f32; 2]) -> Option<[f32; 2]> {
+        let [a, b, c, d] = self.transform;
+        let det = a * d - b * c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (x, y) = (p[0] - self.x - self.width / 2.0, p[1] - self.y - self.height / 2.0);
+        let (x, y) = ((d * x - c * y) / det, (a * y - b * x) / det);
+        let (sin, cos) = self.angle.sin_cos();
+        Some([cos * x + sin * y, cos * y - sin * x])
+    }
+    // Signed distance from a point on screen to what the shape draws, in the shape's own pixels.
+    // points are the polygon points of the Graphics that made the shape. Custom kinds are tested
+    // against their bounds, shadows are never hit.
+    pub fn distance(&self, p: [f32; 2], points: &[[f32; 2]]) -> Option<f32> {
+        let local = self.local_point(p)?;
+        let radii = [self.width / 2.0, self.height / 2.0];
+        let half = self.thickness / 2.0;
+        let polygon = || {
+            let first = self.data[0].max(0) as usize;
+            let count = self.data[1].max(0) as usize;
+            points.get(first..first + count).map(|points| polygon_dist(local, points))
+        };
+        Some(match ShapeKind::try_from(self.kind).ok()? {
+            ShapeKind::FillRect | ShapeKind::TexRect | ShapeKind::NineSlice => box_dist(local, radii),
+            ShapeKind::FillOval | ShapeKind::TexOval => ellipse_dist(local, radii),
+            ShapeKind::Rect => stroke(box_dist(local, radii), self.thickness),
+            ShapeKind::Oval => stroke(ellipse_dist(local, radii), self.thickness),
+            ShapeKind::Arc => stroke(ellipse_dist(local, radii), self.thickness)
+                .max(wedge_dist(local, self.params[0], self.params[1])),
+            ShapeKind::FillPie | ShapeKind::FillRing => arc_dist(local, radii, self.params),
+            ShapeKind::Pie | ShapeKind::Ring => stroke(arc_dist(local, radii, self.params), self.thickness),
+            ShapeKind::Line => length((local[0].abs() - (radii[0] - half)).max(0.0), local[1]) - half,
+            ShapeKind::FillPolygon => polygon()?,
+            ShapeKind::Polygon => stroke(polygon()?, self.thickness),
+            ShapeKind::Polyline => {
+                let first = self.data[0].max(0) as usize;
+                let count = self.data[1].max(0) as usize;
+                polyline_dist(local, points.get(first..first + count)?) - half
+            }
+            ShapeKind::FillRoundRect => round_box_dist(local, radii, self.params[0]),
+            ShapeKind::RoundRect => stroke(round_box_dist(local, radii, self.params[0]), self.thickness),
+            ShapeKind::Shadow => return None,
+            ShapeKind::Custom(_) => box_dist(local, radii),
+        })
+    }
+    // Dash gaps count as part of the outline, so dashed shapes are easy to click.
+    pub fn contains(&self, p: [f32; 2], points: &[[f32; 2]]) -> bool {
+        self.distance(p, points).is_some_and(|d| d <= 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn boxes() {
+        assert!(near(box_dist([0.0,0.0], [10.0,5.0]), -5.0));
+        assert!(near(box_dist([10.0,0.0], [10.0,5.0]), 0.0));
+        assert!(near(box_dist([13.0,9.0], [10.0,5.0]), 5.0));
+        assert!(near(round_box_dist([10.0,5.0], [10.0,5.0], 2.0), 2.0 * 2f32.sqrt() - 2.0));
+        assert!(near(round_box_dist([0.0,5.0], [10.0,5.0], 2.0), 0.0));
+    }
+
+    #[test]
+    fn ellipses() {
+        let r = [20.0,10.0];
+        assert!(near(ellipse_dist([0.0,0.0], r), -10.0));
+        assert!(near(ellipse_dist([20.0,0.0], r), 0.0));
+        assert!(near(ellipse_dist([0.0,-10.0], r), 0.0));
+        assert!(near(ellipse_dist([30.0,0.0], r), 10.0));
+        assert!(near(ellipse_dist([0.0,4.0], r), -6.0));
+        assert!(near(ellipse_dist([5.0,5.0], [10.0,10.0]), 50f32.sqrt() - 10.0));
+        assert!(near(ellipse_dist([0.0,0.0], [10.0,10.0]), -10.0));
+    }
+
+    #[test]
+    fn wedges() {
+        // a quarter from +x clockwise to +y, which points down
+        assert!(wedge_dist([1.0,1.0], 0.0, FRAC_PI_2) < 0.0);
+        assert!(near(wedge_dist([1.0,-1.0], 0.0, FRAC_PI_2), 1.0));
+        assert!(near(wedge_dist([-1.0,1.0], 0.0, FRAC_PI_2), 1.0));
+        assert!(near(wedge_dist([3.0,0.0], 0.0, FRAC_PI_2), 0.0));
+        assert!(wedge_dist([-1.0,-1.0], 0.0, TAU) < 0.0);
+        // pies and rings cut by the wedge and the hole
+        let pie = [0.0, PI, 0.0, 0.0];
+        assert!(arc_dist([0.0,5.0], [10.0,10.0], pie) < 0.0);
+        assert!(arc_dist([0.0,-5.0], [10.0,10.0], pie) > 0.0);
+        let ring = [0.0, TAU, 0.5, 0.0];
+        assert!(near(arc_dist([7.5,0.0], [10.0,10.0], ring), -2.5));
+        assert!(near(arc_dist([0.0,0.0], [10.0,10.0], ring), 5.0));
+    }
+
+    #[test]
+    fn polygons() {
+        let square = [[-5.0,-5.0],[5.0,-5.0],[5.0,5.0],[-5.0,5.0]];
+        assert!(near(polygon_dist([0.0,0.0], &square), -5.0));
+        assert!(near(polygon_dist([5.0,0.0], &square), 0.0));
+        assert!(near(polygon_dist([8.0,9.0], &square), 5.0));
+        // a bow tie crosses itself, the middle of each half is inside
+        let bow = [[-10.0,-5.0],[10.0,5.0],[10.0,-5.0],[-10.0,5.0]];
+        assert!(polygon_dist([-8.0,0.0], &bow) < 0.0);
+        assert!(polygon_dist([0.0,4.0], &bow) > 0.0);
+        assert_eq!(polygon_dist([0.0,0.0], &[]), f32::MAX);
+        assert!(near(polyline_dist([5.0,3.0], &[[0.0,0.0],[10.0,0.0],[10.0,10.0]]), 3.0));
+        assert!(near(polyline_dist([12.0,5.0], &[[0.0,0.0],[10.0,0.0],[10.0,10.0]]), 2.0));
+    }
+
+    #[test]
+    fn local_points_undo_angle_and_transform() {
+        let shape = Shape::fill_rect(0.0,0.0,20.0,10.0);
+        assert_eq!(shape.local_point([10.0,5.0]), Some([0.0,0.0]));
+        let turned = shape.angle(FRAC_PI_2).transform([2.0,0.0,0.0,2.0]);
+        let p = turned.local_point([10.0,25.0]).unwrap();
+        assert!(near(p[0], 10.0) && near(p[1], 0.0));
+        assert_eq!(shape.transform([0.0,0.0,0.0,1.0]).local_point([10.0,5.0]), None);
+    }
+
+    #[test]
+    fn contains_follows_the_kind() {
+        let rect = Shape::fill_rect(0.0,0.0,20.0,10.0);
+        assert!(rect.contains([1.0,1.0], &[]));
+        assert!(!rect.contains([25.0,5.0], &[]));
+        assert!(rect.transform([2.0,0.0,0.0,1.0]).contains([25.0,5.0], &[]));
+        assert!(rect.angle(FRAC_PI_2).contains([10.0,14.0], &[]));
+        assert!(!rect.angle(FRAC_PI_2).contains([19.0,5.0], &[]));
+
+        // outlines only cover their border
+        let outline = Shape::rect(0.0,0.0,20.0,20.0).thickness(4.0);
+        assert!(!outline.contains([10.0,10.0], &[]));
+        assert!(outline.contains([1.0,10.0], &[]));
+        let oval = Shape::oval(0.0,0.0,40.0,20.0).thickness(2.0);
+        assert!(oval.contains([39.5,10.0], &[]));
+        assert!(!oval.contains([20.0,10.0], &[]));
+
+        let line = Shape::line(0.0,0.0,10.0,0.0,2.0);
+        assert!(line.contains([5.0,0.5], &[]));
+        assert!(line.contains([-0.5,0.0], &[]));
+        assert!(!line.contains([5.0,2.0], &[]));
+
+        let points = [[0.0,0.0],[-5.0,-5.0],[5.0,-5.0],[5.0,5.0],[-5.0,5.0]];
+        let polygon = Shape::fill_polygon(0.0,0.0,10.0,10.0,1,4);
+        assert!(polygon.contains([5.0,5.0], &points));
+        assert!(!polygon.contains([12.0,5.0], &points));
+        assert_eq!(Shape::fill_polygon(0.0,0.0,10.0,10.0,3,4).distance([5.0,5.0], &points), None);
+
+        let pie = Shape::fill_pie(0.0,0.0,20.0,20.0,0.0,FRAC_PI_2);
+        assert!(pie.contains([15.0,15.0], &[]));
+        assert!(!pie.contains([5.0,5.0], &[]));
+
+        let shadow = Shape::shadow(&rect, &crate::paint::Shadow::new(0.0,0.0,4.0)).unwrap();
+        assert!(!shadow.contains([10.0,5.0], &[]));
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{anyhow, Result};
+
+// Just enough json for the sprite data exported by art tools. Objects keep their keys in order,
+// since frame order in a json hash is the animation order.
+#[derive(Clone,Debug,PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn parse(text: &str) -> Result<Json> {
+        let mut parser = Parser { text: text.as_bytes(), at: 0 };
+        let value = parser.value()?;
+        parser.space();
+        if parser.at < parser.text.len() {
+            return Err(anyhow!("Unexpected json after the value at byte {}", parser.at));
+        }
+        Ok(value)
+    }
+    // Null for missing keys and for values that aren't objects.
+    pub(crate) fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map_or(&Json::Null, |(_, v)| v),
+            _ => &Json::Null,
+        }
+    }
+    pub(crate) fn as_f32(&self) -> Option<f32> {
+        match self {
+            Json::Number(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn space(&mut self) {
+        while self.at < self.text.len() && self.text[self.at].is_ascii_whitespace() {
+            self.at += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<u8> {
+        self.space();
+        self.text.get(self.at).copied()
+    }
+    fn expect(&mut self, c: u8) -> Result<()> {
+        if self.peek() != Some(c) {
+            return Err(anyhow!("Expected '{}' in json at byte {}", c as char, self.at));
+        }
+        self.at += 1;
+        Ok(())
+    }
+    fn word(&mut self, word: &str, value: Json) -> Result<Json> {
+        if !self.text[self.at..].starts_with(word.as_bytes()) {
+            return Err(anyhow!("Unexpected json at byte {}", self.at));
+        }
+        self.at += word.len();
+        Ok(value)
+    }
+    fn value(&mut self) -> Result<Json> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.word("true", Json::Bool(true)),
+            Some(b'f') => self.word("false", Json::Bool(false)),
+            Some(b'n') => self.word("null", Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(anyhow!("Unexpected json at byte {}", self.at)),
+            None => Err(anyhow!("Json ended early")),
+        }
+    }
+    fn object(&mut self) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut pairs = vec![];
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Json::Object(pairs));
+        }
+        loop {
+            self.space();
+            let key = self.string()?;
+            self.expect(b':')?;
+            pairs.push((key, self.value()?));
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                _ => break,
+            }
+        }
+        self.expect(b'}')?;
+        Ok(Json::Object(pairs))
+    }
+    fn array(&mut self) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut values = vec![];
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                _ => break,
+            }
+        }
+        self.expect(b']')?;
+        Ok(Json::Array(values))
+    }
+    fn number(&mut self) -> Result<Json> {
+        let start = self.at;
+        while self.at < self.text.len() && matches!(self.text[self.at], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.at]).ok()
+            .and_then(|n| n.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| anyhow!("Bad json number at byte {}", start))
+    }
+    fn hex(&mut self) -> Result<u32> {
+        let digits = self.text.get(self.at..self.at + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| anyhow!("Bad json escape at byte {}", self.at))?;
+        self.at += 4;
+        Ok(digits)
+    }
+    fn string(&mut self) -> Result<String> {
+        if self.text.get(self.at) != Some(&b'"') {
+            return Err(anyhow!("Expected a json string at byte {}", self.at));
+        }
+        self.at += 1;
+        let mut bytes = vec![];
+        loop {
+            let c = *self.text.get(self.at).ok_or_else(|| anyhow!("Json string never ends"))?;
+            self.at += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = *self.text.get(self.at).ok_or_else(|| anyhow!("Json string never ends"))?;
+                    self.at += 1;
+                    let c = match e {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            // surrogate pairs
+                            if (0xd800..0xdc00).contains(&code) && self.text[self.at..].starts_with(b"\\u") {
+                                self.at += 2;
+                                code = 0x10000 + ((code - 0xd800) << 10) + (self.hex()?.wrapping_sub(0xdc00) & 0x3ff);
+                            }
+                            char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        e => e as char,
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|e| anyhow!("Json string isn't utf-8: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_values_in_order() {
+        let json = Json::parse(r#" {"b": [1, -2.5e1, true, null], "a": {"c": false}} "#).unwrap();
+        assert_eq!(json, Json::Object(vec![
+            ("b".to_string(), Json::Array(vec![Json::Number(1.0), Json::Number(-25.0), Json::Bool(true), Json::Null])),
+            ("a".to_string(), Json::Object(vec![("c".to_string(), Json::Bool(false))])),
+        ]));
+        assert_eq!(json.get("a").get("c").as_bool(), Some(false));
+        assert_eq!(json.get("missing"), &Json::Null);
+        assert_eq!(json.get("b").get("x"), &Json::Null);
+        assert_eq!(Json::parse("[]").unwrap(), Json::Array(vec![]));
+        assert_eq!(Json::parse("{}").unwrap(), Json::Object(vec![]));
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        let json = Json::parse(r#""a\"b\\c\/d\n\t""#).unwrap();
+        assert_eq!(json.as_str(), Some("a\"b\\c/d\n\t"));
+        // non ascii bytes pass through as they are
+        assert_eq!(Json::parse("\"é中\"").unwrap().as_str(), Some("é中"));
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        assert_eq!(Json::parse(r#""\ud83d\ude00 \u00e9""#).unwrap().as_str(), Some("😀 é"));
+        // a lone half can't be a char
+        assert_eq!(Json::parse(r#""\ud83d""#).unwrap().as_str(), Some("\u{fffd}"));
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            r#"{a: 1}"#,
+            r#""never ends"#,
+            r#""\u12""#,
+            "tru",
+            "1-2",
+            "{} []",
+        ] {
+            assert!(Json::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+
+// What a shape instance draws. The GPU sees it as a KindId, the cases of the switch in shape_shader.wgsl.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub enum ShapeKind {
+    FillRect,
+    FillOval,
+    Rect,
+    Oval,
+    TexRect,
+    TexOval,
+    Arc,
+    FillPie,
+    Pie,
+    FillRing,
+    Ring,
+    Line,
+    FillPolygon,
+    Polygon,
+    FillRoundRect,
+    RoundRect,
+    Shadow,
+    // A texture stretched in nine pieces, see Graphics::nine_slice.
+    NineSlice,
+    // Connected line segments drawn as one shape, so joints aren't covered twice.
+    Polyline,
+    // Registered with a ShapeRegistry, see ShapeRegistry::register.
+    Custom(u16),
+}
+
+// Encoded kind as it is stored in the instance buffer. Any i32 can be one, so turning it back into a
+// ShapeKind can fail.
+#[repr(transparent)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash,bytemuck::Pod,bytemuck::Zeroable)]
+pub struct KindId(i32);
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct InvalidKind(pub i32);
+
+impl std::fmt::Display for InvalidKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a shape kind", self.0)
+    }
+}
+
+impl std::error::Error for InvalidKind {}
+
+// Wgsl of a custom kind that doesn't compile, with the compiler's message.
+#[derive(Clone,Debug,PartialEq)]
+pub struct InvalidShape(pub String);
+
+impl std::fmt::Display for InvalidShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "shape kind doesn't compile: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidShape {}
+
+impl ShapeKind {
+    const BUILT_IN: [ShapeKind; 19] = [
+        ShapeKind::FillRect, ShapeKind::FillOval, ShapeKind::Rect, ShapeKind::Oval,
+        ShapeKind::TexRect, ShapeKind::TexOval, ShapeKind::Arc, ShapeKind::FillPie,
+        ShapeKind::Pie, ShapeKind::FillRing, ShapeKind::Ring, ShapeKind::Line,
+        ShapeKind::FillPolygon, ShapeKind::Polygon, ShapeKind::FillRoundRect, ShapeKind::RoundRect,
+        ShapeKind::Shadow, ShapeKind::NineSlice, ShapeKind::Polyline,
+    ];
+    // Custom kinds are numbered after this, leaving room for more built in kinds.
+    const FIRST_CUSTOM: i32 = 256;
+
+    pub fn id(self) -> KindId {
+        KindId(match self {
+            ShapeKind::Custom(n) => Self::FIRST_CUSTOM + n as i32,
+            kind => Self::BUILT_IN.iter().position(|k| *k == kind).unwrap() as i32,
+        })
+    }
+    // How shape_shader.wgsl names a built in kind, KIND_ and the name in capitals, like KIND_FILL_RECT.
+    fn shader_name(self) -> Option<String> {
+        if self.is_custom() {
+            return None;
+        }
+        let mut name = String::from("KIND");
+        for c in format!("{:?}", self).chars() {
+            if c.is_uppercase() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_uppercase());
+        }
+        Some(name)
+    }
+    pub fn is_custom(self) -> bool {
+        matches!(self, ShapeKind::Custom(_))
+    }
+    // Outline kinds only cover their border.
+    pub fn is_outline(self) -> bool {
+        matches!(self, ShapeKind::Rect | ShapeKind::Oval | ShapeKind::Arc | ShapeKind::Pie
+            | ShapeKind::Ring | ShapeKind::Line | ShapeKind::Polygon | ShapeKind::RoundRect | ShapeKind::Polyline)
+    }
+}
+
+impl KindId {
+    pub fn code(self) -> i32 {
+        self.0
+    }
+}
+
+impl TryFrom<KindId> for ShapeKind {
+    type Error = InvalidKind;
+    fn try_from(id: KindId) -> Result<Self, Self::Error> {
+        ShapeKind::try_from(id.0)
+    }
+}
+
+impl TryFrom<i32> for ShapeKind {
+    type Error = InvalidKind;
+    fn try_from(code: i32) -> Result<Self, Self::Error> {
+        if code >= Self::FIRST_CUSTOM && code - Self::FIRST_CUSTOM <= u16::MAX as i32 {
+            return Ok(ShapeKind::Custom((code - Self::FIRST_CUSTOM) as u16));
+        }
+        usize::try_from(code).ok()
+            .and_then(|i| Self::BUILT_IN.get(i).copied())
+            .ok_or(InvalidKind(code))
+    }
+}
+
+// Wgsl for a custom kind. The functions can use everything in shape_shader.wgsl, like the
+// VertexOutput fields and the distance helpers box_dist, ellipse_dist, stroke, coverage and fade.
+#[derive(Clone,Debug,PartialEq)]
+pub enum ShapeFunction {
+    // Body of fn(p: vec2<f32>, r: vec2<f32>, in: VertexOutput) -> f32, the signed distance in pixels
+    // from p to the shape, negative inside. p is relative to the center, r is half the size.
+    Fill(String),
+    // The same distance function, drawn as an outline thickness pixels wide.
+    Outline(String),
+    // Body of fn(f: Fragment, in: VertexOutput) -> vec4<f32>, for full control of the color.
+    Color(String),
+}
+
+#[derive(Clone,Debug)]
+pub struct ShapeDefinition {
+    pub name: String,
+    pub function: ShapeFunction,
+}
+
+// Custom shape kinds, compiled into the shape shader. Registering after the first frame rebuilds the pipeline.
+#[derive(Debug,Default)]
+pub struct ShapeRegistry {
+    definitions: Vec<ShapeDefinition>,
+    pub(crate) changed: bool,
+}
+
+impl ShapeRegistry {
+    pub fn new() -> Self {
+        Self { definitions: vec![], changed: false }
+    }
+    // Registering the same name again replaces its function and keeps the kind.
+    pub fn register(&mut self, name: &str, function: ShapeFunction) -> ShapeKind {
+        self.changed = true;
+        if let Some(index) = self.definitions.iter().position(|d| d.name == name) {
+            self.definitions[index].function = function;
+            return ShapeKind::Custom(index as u16);
+        }
+        self.definitions.push(ShapeDefinition { name: name.to_string(), function });
+        ShapeKind::Custom((self.definitions.len() - 1) as u16)
+    }
+    // Takes back the kind registered last, when its wgsl didn't compile.
+    pub(crate) fn remove_last(&mut self) {
+        self.definitions.pop();
+    }
+    pub fn get(&self, name: &str) -> Option<ShapeKind> {
+        self.definitions.iter()
+            .position(|d| d.name == name)
+            .map(|i| ShapeKind::Custom(i as u16))
+    }
+    pub fn definition(&self, kind: ShapeKind) -> Option<&ShapeDefinition> {
+        match kind {
+            ShapeKind::Custom(n) => self.definitions.get(n as usize),
+            _ => None,
+        }
+    }
+    // The shape shader with encode_srgb, the custom functions and their switch cases spliced in.
+    pub(crate) fn shader_source(&self, template: &str) -> String {
+        let mut functions = String::new();
+        let mut cases = String::new();
+        for (i, def) in self.definitions.iter().enumerate() {
+            let code = ShapeKind::Custom(i as u16).id().code();
+            functions.push_str(&format!("// {}\n", def.name));
+            match &def.function {
+                ShapeFunction::Fill(body) | ShapeFunction::Outline(body) => {
+                    let d = match &def.function {
+                        ShapeFunction::Fill(_) => format!("custom_{}_dist(f.local, f.radii, in)", i),
+                        _ => format!("stroke(custom_{}_dist(f.local, f.radii, in), in.thickness)", i),
+                    };
+                    functions.push_str(&format!(
+                        "fn custom_{i}_dist(p: vec2<f32>, r: vec2<f32>, in: VertexOutput) -> f32 {{\n{body}\n}}\n\
+                         fn custom_{i}(f: Fragment, in: VertexOutput) -> vec4<f32> {{\n    return fade(f.color, coverage({d}, f.pixel));\n}}\n",
+                    ));
+                }
+                ShapeFunction::Color(body) => {
+                    functions.push_str(&format!("fn custom_{i}(f: Fragment, in: VertexOutput) -> vec4<f32> {{\n{body}\n}}\n"));
+                }
+            }
+            cases.push_str(&format!("        case {}: {{ // {}\n            return custom_{}(f, in);\n        }}\n", code, def.name, i));
+        }
+        let source = splice(template, "// ENCODE SRGB", crate::renderer::ENCODE_SRGB);
+        let source = splice(&source, "// CUSTOM SHAPE FUNCTIONS", &functions);
+        let source = splice(&source, "// CUSTOM SHAPE KINDS", &cases);
+        kind_numbers(&source)
+    }
+}
+
+// Replaces the line that is only marker, whatever its indentation and line ending.
+pub(crate) fn splice(source: &str, marker: &str, text: &str) -> String {
+    let mut found = false;
+    let mut out = String::with_capacity(source.len() + text.len());
+    for line in source.lines() {
+        if line.trim() == marker {
+            out.push_str(text);
+            found = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    assert!(found, "the shader has no {} line", marker);
+    out
+}
+
+// Replaces the KIND_ names of built in kinds with their numbers, wgsl switch cases have to be literals.
+fn kind_numbers(source: &str) -> String {
+    let names: std::collections::HashMap<String, i32> = ShapeKind::BUILT_IN.iter()
+        .filter_map(|kind| Some((kind.shader_name()?, kind.id().code())))
+        .collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    source.split_inclusive(|c: char| !is_word(c))
+        .map(|piece| {
+            let word = piece.trim_end_matches(|c: char| !is_word(c));
+            match names.get(word) {
+                Some(code) => format!("{}{}", code, &piece[word.len()..]),
+                None => piece.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(source: &str) {
+        let module = naga::front::wgsl::parse_str(source).unwrap_or_else(|e| panic!("{}", e.emit_to_string(source)));
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+
+    #[test]
+    fn kinds_round_trip() {
+        for kind in ShapeKind::BUILT_IN.iter().copied().chain([ShapeKind::Custom(0), ShapeKind::Custom(u16::MAX)]) {
+            assert_eq!(ShapeKind::try_from(kind.id()), Ok(kind));
+        }
+        assert_eq!(ShapeKind::FillRect.id().code(), 0);
+        assert_eq!(ShapeKind::Custom(3).id().code(), 259);
+    }
+
+    #[test]
+    fn unknown_codes_are_errors() {
+        for code in [-1, ShapeKind::BUILT_IN.len() as i32, 255, 256 + 65536, i32::MAX] {
+            assert_eq!(ShapeKind::try_from(code), Err(InvalidKind(code)));
+        }
+    }
+
+    #[test]
+    fn shader_names() {
+        assert_eq!(ShapeKind::FillRoundRect.shader_name().as_deref(), Some("KIND_FILL_ROUND_RECT"));
+        assert_eq!(ShapeKind::Rect.shader_name().as_deref(), Some("KIND_RECT"));
+        assert_eq!(ShapeKind::Custom(1).shader_name(), None);
+    }
+
+    #[test]
+    fn kind_names_are_whole_words() {
+        let source = kind_numbers("case KIND_RECT, KIND_FILL_RECT: {} // KIND_RECTS KIND_SHADOW\n");
+        assert_eq!(source, "case 2, 0: {} // KIND_RECTS 16\n");
+    }
+
+    #[test]
+    fn markers_match_any_indentation_and_line_ending() {
+        let source = splice("a\r\n\t  // MARK  \r\nb", "// MARK", "x\n");
+        assert_eq!(source, "a\nx\nb\n");
+    }
+
+    #[test]
+    #[should_panic]
+    fn missing_markers_panic() {
+        splice("a\nb\n", "// MARK", "x\n");
+    }
+
+    #[test]
+    fn shape_shader_compiles() {
+        let mut registry = ShapeRegistry::new();
+        registry.register("diamond", ShapeFunction::Fill("    return abs(p.x) / r.x + abs(p.y) / r.y - 1.0;".to_string()));
+        registry.register("cross", ShapeFunction::Outline("    return min(abs(p.x), abs(p.y)) - 2.0;".to_string()));
+        registry.register("tint", ShapeFunction::Color("    return f.texel * f.color;".to_string()));
+        let source = registry.shader_source(crate::renderer::SHAPE_SHADER);
+        assert!(ShapeKind::BUILT_IN.iter().all(|kind| !source.contains(&kind.shader_name().unwrap())));
+        assert!(source.contains("case 258: { // tint"));
+        validate(&source);
+        validate(&ShapeRegistry::new().shader_source(&crate::renderer::SHAPE_SHADER.replace('\n', "\r\n")));
+    }
+
+    #[test]
+    fn mesh_shader_compiles() {
+        validate(&crate::renderer::mesh_shader_source());
+    }
+}
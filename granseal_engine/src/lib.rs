@@ -20,7 +20,21 @@ use crate::shape::*;
 use crate::texture::{Texture, TextureInfo};
 
 mod texture;
+mod json;
+mod watch;
+mod loader;
+pub mod assets;
 pub mod shape;
+pub mod path;
+pub mod paint;
+pub mod transform;
+pub mod kinds;
+pub mod hit;
+pub mod scene;
+pub mod particles;
+pub mod sprites;
+pub mod pixels;
+pub mod atlas;
 pub mod events;
 pub mod renderer;
 
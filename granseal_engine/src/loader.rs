@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+use std::sync::{mpsc, Arc, Mutex};
+use image::ImageResult;
+use crate::assets::Assets;
+use crate::paint::TextureOptions;
+use crate::texture::TextureData;
+
+// Decodes images and makes their mip levels on a few threads of their own, so the render thread
+// only uploads them. The threads stop when the loader is dropped.
+#[derive(Debug)]
+pub(crate) struct Loader {
+    jobs: mpsc::Sender<(String, TextureOptions)>,
+    done: mpsc::Receiver<(String, ImageResult<TextureData>)>,
+    pending: HashSet<String>,
+    // pending images unloaded since they were requested, dropped when they arrive
+    cancelled: HashSet<String>,
+}
+
+impl Loader {
+    const MAX_THREADS: usize = 4;
+
+    pub(crate) fn new(assets: Assets) -> Self {
+        let (jobs, queue) = mpsc::channel::<(String, TextureOptions)>();
+        let (sender, done) = mpsc::channel();
+        let queue = Arc::new(Mutex::new(queue));
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(Loader::MAX_THREADS);
+        for i in 0..threads {
+            let queue = queue.clone();
+            let sender = sender.clone();
+            let assets = assets.clone();
+            std::thread::Builder::new()
+                .name(format!("granseal image loader {}", i))
+                .spawn(move || loop {
+                    // the lock is only held while waiting for a job, not while decoding it
+                    let (path, options) = match queue.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+                    let data = assets.open_image(&path).map(|image| TextureData::new(&image, &options));
+                    if sender.send((path, data)).is_err() {
+                        return;
+                    }
+                })
+                .expect("Couldn't start an image loader.");
+        }
+        Self { jobs, done, pending: HashSet::new(), cancelled: HashSet::new() }
+    }
+    // Queues an image unless it's already on its way.
+    pub(crate) fn request(&mut self, path: &str, options: TextureOptions) {
+        self.cancelled.remove(path);
+        if self.pending.insert(path.to_string()) {
+            let _ = self.jobs.send((path.to_string(), options));
+        }
+    }
+    pub(crate) fn cancel(&mut self, path: &str) {
+        if self.pending.contains(path) {
+            self.cancelled.insert(path.to_string());
+        }
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len() - self.cancelled.len()
+    }
+    // The next image ready to upload, if there is one.
+    pub(crate) fn next(&mut self) -> Option<(String, ImageResult<TextureData>)> {
+        loop {
+            let (path, data) = self.done.try_recv().ok()?;
+            self.pending.remove(&path);
+            if !self.cancelled.remove(&path) {
+                return Some((path, data));
+            }
+        }
+    }
+}
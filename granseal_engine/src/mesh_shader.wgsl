@@ -0,0 +1,34 @@
+// Vertex colored triangles from Graphics::mesh, positions are pixels with y down.
+struct VertexInput {
+    @location(0) pos: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+@group(0) @binding(0)
+var<uniform> screen: vec2<f32>;
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+@group(1) @binding(1)
+var s: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(in.pos.x / screen.x * 2.0 - 1.0, 1.0 - in.pos.y / screen.y * 2.0, 0.0, 1.0);
+    out.uv = in.uv;
+    out.color = in.color;
+    return out;
+}
+
+// ENCODE SRGB
+
+// Untextured meshes are bound to a white texture.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color * encode_srgb(textureSample(t, s, in.uv));
+}
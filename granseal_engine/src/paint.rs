@@ -0,0 +1,266 @@
+use crate::shape::Color;
+
+pub const PAINT_SOLID: i32 = 0;
+pub const PAINT_LINEAR: i32 = 1;
+pub const PAINT_RADIAL: i32 = 2;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum GradientKind {
+    // Angle in radians, clockwise from the positive x axis. The gradient spans the shape's bounds.
+    Linear { angle: f32 },
+    // Center and radius relative to the shape's bounds, a radius of 0.5 reaches the edges.
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    pub fn linear(angle: f32) -> Self {
+        Self { kind: GradientKind::Linear { angle }, stops: vec![] }
+    }
+    pub fn radial(cx: f32, cy: f32, radius: f32) -> Self {
+        Self { kind: GradientKind::Radial { center: [cx,cy], radius }, stops: vec![] }
+    }
+    // Offset goes from 0.0 at the start of the gradient to 1.0 at the end, stops are kept sorted.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        let offset = offset.clamp(0.0,1.0);
+        let index = self.stops.iter().position(|(o,_)| *o > offset).unwrap_or(self.stops.len());
+        self.stops.insert(index,(offset,color));
+        self
+    }
+    // Per instance values for the shader, [cos, sin, 0, 0] for linear and [cx, cy, radius, 0] for radial.
+    pub(crate) fn encode(&self) -> (i32, [f32; 4]) {
+        match self.kind {
+            GradientKind::Linear { angle } => (PAINT_LINEAR, [angle.cos(), angle.sin(), 0.0, 0.0]),
+            GradientKind::Radial { center, radius } => (PAINT_RADIAL, [center[0], center[1], radius.max(0.0001), 0.0]),
+        }
+    }
+}
+
+// Matches the Stop struct in shape_shader.wgsl, padded to the 16 byte alignment of storage arrays.
+#[repr(C)]
+#[derive(Copy,Clone,Debug,bytemuck::Pod,bytemuck::Zeroable)]
+pub struct GradientStop {
+    pub color: [f32; 4],
+    pub offset: f32,
+    _padding: [f32; 3],
+}
+
+impl GradientStop {
+    pub fn new(offset: f32, color: Color) -> Self {
+        Self {
+            color: [color.r, color.g, color.b, color.a],
+            offset,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+// Dash pattern for rect, round rect, oval, arc and line outlines, lengths in pixels along the stroke.
+// Moving offset over time makes the dashes crawl, marching ants style.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Dash {
+    pub dash: f32,
+    pub gap: f32,
+    pub offset: f32,
+    pub round: bool,
+}
+
+impl Dash {
+    pub fn new(dash: f32, gap: f32) -> Self {
+        Self { dash: dash.max(0.0), gap: gap.max(0.0), offset: 0.0, round: false }
+    }
+    // Round dots spaced gap apart, sized by the outline thickness.
+    pub fn dotted(gap: f32) -> Self {
+        Self::new(0.0, gap).round(true)
+    }
+    pub fn offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+    pub fn round(mut self, round: bool) -> Self {
+        self.round = round;
+        self
+    }
+}
+
+// A blurred copy of a filled shape drawn behind it. With no offset and some spread it makes a glow.
+// Images cast the shadow of their bounds. Outlines, lines, meshes and custom kinds cast none.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Shadow {
+    pub offset: [f32; 2],
+    // Like css, the blur radius is twice the standard deviation of the gaussian.
+    pub blur: f32,
+    pub spread: f32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn new(dx: f32, dy: f32, blur: f32) -> Self {
+        Self { offset: [dx,dy], blur: blur.max(0.0), spread: 0.0, color: Color::new(0.0,0.0,0.0,0.5) }
+    }
+    pub fn glow(blur: f32, color: Color) -> Self {
+        Self::new(0.0, 0.0, blur).color(color)
+    }
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+}
+
+// How texture coordinates outside the image are treated.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Wrap {
+    Clamp,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl Wrap {
+    pub(crate) fn code(self) -> i32 {
+        match self {
+            Wrap::Clamp => 0,
+            Wrap::Repeat => 1,
+            Wrap::MirrorRepeat => 2,
+        }
+    }
+    pub(crate) fn address_mode(self) -> wgpu::AddressMode {
+        match self {
+            Wrap::Clamp => wgpu::AddressMode::ClampToEdge,
+            Wrap::Repeat => wgpu::AddressMode::Repeat,
+            Wrap::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Filter {
+    // Crisp texels, for pixel art.
+    Nearest,
+    // Blends neighbouring texels, and mip levels when there are mipmaps.
+    Linear,
+}
+
+impl Filter {
+    pub(crate) fn mode(self) -> wgpu::FilterMode {
+        match self {
+            Filter::Nearest => wgpu::FilterMode::Nearest,
+            Filter::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+// How texels are stored on the gpu.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum TextureFormat {
+    // 4 bytes a pixel, for images as they come from png and jpeg files.
+    Srgb8,
+    // Linear values past 0..1 for hdr data, 8 and 16 bytes a pixel. Float32 is Float16 on
+    // adapters that can't filter it. 8 bit images are converted from sRGB, float images are
+    // taken as linear already.
+    Float16,
+    Float32,
+}
+
+impl TextureFormat {
+    pub(crate) fn wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            TextureFormat::Srgb8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Float16 => wgpu::TextureFormat::Rgba16Float,
+            TextureFormat::Float32 => wgpu::TextureFormat::Rgba32Float,
+        }
+    }
+    pub(crate) fn bytes_per_pixel(self) -> u32 {
+        match self {
+            TextureFormat::Srgb8 => 4,
+            TextureFormat::Float16 => 8,
+            TextureFormat::Float32 => 16,
+        }
+    }
+}
+
+// How a texture is stored and sampled, see Graphics::set_texture_options.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct TextureOptions {
+    pub format: TextureFormat,
+    pub filter: Filter,
+    // Wrapping of mesh uvs outside 0..1, shapes wrap with Tiling instead.
+    pub wrap: Wrap,
+    // Smaller copies of the image for drawing it scaled down without shimmering.
+    pub mipmaps: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self { format: TextureFormat::Srgb8, filter: Filter::Nearest, wrap: Wrap::Clamp, mipmaps: false }
+    }
+}
+
+impl TextureOptions {
+    // Linear filtering with mipmaps, for photos and high resolution art.
+    pub fn smooth() -> Self {
+        Self { format: TextureFormat::Srgb8, filter: Filter::Linear, wrap: Wrap::Clamp, mipmaps: true }
+    }
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+}
+
+// An image repeated over an area. offset moves the pattern in pixels, scale sizes one tile
+// relative to the image, so scrolling a background is a matter of changing the offset.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Tiling {
+    pub offset: [f32; 2],
+    pub scale: [f32; 2],
+    pub wrap: Wrap,
+}
+
+impl Tiling {
+    pub fn new(wrap: Wrap) -> Self {
+        Self { offset: [0.0,0.0], scale: [1.0,1.0], wrap }
+    }
+    pub fn repeat() -> Self {
+        Self::new(Wrap::Repeat)
+    }
+    pub fn mirror() -> Self {
+        Self::new(Wrap::MirrorRepeat)
+    }
+    pub fn offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = [x,y];
+        self
+    }
+    pub fn scale(mut self, sx: f32, sy: f32) -> Self {
+        self.scale = [sx,sy];
+        self
+    }
+    // Texture coordinate offset and scale for a tiled area of width by height showing an image
+    // of image_width by image_height.
+    pub(crate) fn encode(&self, width: f32, height: f32, image_width: f32, image_height: f32) -> [f32; 4] {
+        let tile = [
+            (image_width * self.scale[0]).max(0.0001),
+            (image_height * self.scale[1]).max(0.0001),
+        ];
+        [-self.offset[0] / tile[0], -self.offset[1] / tile[1], width / tile[0], height / tile[1]]
+    }
+}
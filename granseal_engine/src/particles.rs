@@ -0,0 +1,230 @@
+use std::time::Duration;
+use crate::shape::{Color, Graphics, Quad, Shape, ShapeKind, Vertex};
+
+#[derive(Copy,Clone,Debug)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    age: f32,
+    lifetime: f32,
+    rotation: f32,
+    spin: f32,
+}
+
+// Spawns particles at its position and moves them, call update on Event::Update and draw on Event::Draw.
+// Ranges are (min, max) and picked from evenly. Particles stay where they were spawned when the emitter moves.
+#[derive(Clone,Debug)]
+pub struct Emitter {
+    pub position: [f32; 2],
+    // Particles per second, 0 only spawns on burst.
+    pub rate: f32,
+    // Seconds.
+    pub lifetime: (f32, f32),
+    // Pixels per second.
+    pub speed: (f32, f32),
+    // Angle in radians, particles leave within spread / 2 of it either way.
+    pub direction: f32,
+    pub spread: f32,
+    // Pixels per second per second.
+    pub gravity: [f32; 2],
+    // Radians per second.
+    pub spin: (f32, f32),
+    // At spawn and at the end of the lifetime, blended between.
+    pub colors: (Color, Color),
+    pub sizes: (f32, f32),
+    // Kind of the untextured particles.
+    pub kind: ShapeKind,
+    // Textured particles are drawn together as one mesh.
+    pub image: Option<String>,
+    // Stops spawning, particles already out live on.
+    pub emitting: bool,
+    pub max_particles: usize,
+    particles: Vec<Particle>,
+    spawn: f32,
+    seed: u32,
+}
+
+impl Emitter {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            position: [x,y],
+            rate: 10.0,
+            lifetime: (1.0,1.0),
+            speed: (50.0,100.0),
+            direction: 0.0,
+            spread: std::f32::consts::TAU,
+            gravity: [0.0,0.0],
+            spin: (0.0,0.0),
+            colors: (Color::WHITE,Color::new(1.0,1.0,1.0,0.0)),
+            sizes: (8.0,8.0),
+            kind: ShapeKind::FillOval,
+            image: None,
+            emitting: true,
+            max_particles: 10_000,
+            particles: vec![],
+            spawn: 0.0,
+            seed: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(1, |d| d.subsec_nanos() | 1),
+        }
+    }
+    pub fn rate(mut self, rate: f32) -> Self {
+        self.rate = rate.max(0.0);
+        self
+    }
+    pub fn lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min,max);
+        self
+    }
+    pub fn speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min,max);
+        self
+    }
+    pub fn direction(mut self, angle: f32, spread: f32) -> Self {
+        self.direction = angle;
+        self.spread = spread;
+        self
+    }
+    pub fn gravity(mut self, x: f32, y: f32) -> Self {
+        self.gravity = [x,y];
+        self
+    }
+    pub fn spin(mut self, min: f32, max: f32) -> Self {
+        self.spin = (min,max);
+        self
+    }
+    pub fn colors(mut self, start: Color, end: Color) -> Self {
+        self.colors = (start,end);
+        self
+    }
+    pub fn sizes(mut self, start: f32, end: f32) -> Self {
+        self.sizes = (start,end);
+        self
+    }
+    pub fn kind(mut self, kind: ShapeKind) -> Self {
+        self.kind = kind;
+        self
+    }
+    pub fn image(mut self, image: &str) -> Self {
+        self.image = Some(image.to_string());
+        self
+    }
+    pub fn max_particles(mut self, max: usize) -> Self {
+        self.max_particles = max;
+        self
+    }
+    // Same seed, same particles.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.seed = seed.max(1);
+        self
+    }
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.spawn = 0.0;
+    }
+    // xorshift, 0..1
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1u32 << 24) as f32
+    }
+    fn between(&mut self, range: (f32, f32)) -> f32 {
+        range.0 + (range.1 - range.0) * self.random()
+    }
+    // Spawns count particles at once, also while not emitting.
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count.min(self.max_particles.saturating_sub(self.particles.len())) {
+            let angle = self.direction + (self.random() - 0.5) * self.spread;
+            let speed = self.between(self.speed);
+            let particle = Particle {
+                position: self.position,
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+                age: 0.0,
+                lifetime: self.between(self.lifetime).max(0.0001),
+                rotation: angle,
+                spin: self.between(self.spin),
+            };
+            self.particles.push(particle);
+        }
+    }
+    pub fn update(&mut self, delta: &Duration) {
+        let dt = delta.as_secs_f32();
+        let gravity = self.gravity;
+        for p in &mut self.particles {
+            p.age += dt;
+            p.velocity = [p.velocity[0] + gravity[0] * dt, p.velocity[1] + gravity[1] * dt];
+            p.position = [p.position[0] + p.velocity[0] * dt, p.position[1] + p.velocity[1] * dt];
+            p.rotation += p.spin * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+        if self.emitting {
+            self.spawn += self.rate * dt;
+            let count = self.spawn.floor();
+            self.spawn -= count;
+            self.burst(count as usize);
+        }
+    }
+    // Color and size of a particle, t goes 0..1 over its life.
+    fn look(&self, t: f32) -> (Color, f32) {
+        let (a, b) = self.colors;
+        let color = Color::new(
+            a.r + (b.r - a.r) * t,
+            a.g + (b.g - a.g) * t,
+            a.b + (b.b - a.b) * t,
+            a.a + (b.a - a.a) * t,
+        );
+        (color, self.sizes.0 + (self.sizes.1 - self.sizes.0) * t)
+    }
+    // Adds the particles to g under its current transform, untextured ones as shape instances,
+    // textured ones as a single mesh.
+    pub fn draw(&self, g: &mut Graphics) {
+        match &self.image {
+            None => {
+                for p in &self.particles {
+                    let (color, size) = self.look(p.age / p.lifetime);
+                    let (x,y,m) = g.apply_transform(p.position[0] - size / 2.0, p.position[1] - size / 2.0, size, size);
+                    g.shapes.push(
+                        Shape::fill_rect(x,y,size,size)
+                            .kind(self.kind)
+                            .color(color)
+                            .opacity(color.a)
+                            .angle(p.rotation)
+                            .transform(m)
+                    );
+                }
+            }
+            Some(image) => {
+                let mut vertices = Vec::with_capacity(self.particles.len() * 4);
+                let mut indices = Vec::with_capacity(self.particles.len() * 6);
+                for p in &self.particles {
+                    let (color, size) = self.look(p.age / p.lifetime);
+                    let (sin, cos) = p.rotation.sin_cos();
+                    let h = size / 2.0;
+                    let corner = |x: f32, y: f32, u: f32, v: f32| Vertex::new(
+                        p.position[0] + cos * x - sin * y,
+                        p.position[1] + sin * x + cos * y,
+                        u, v, color,
+                    );
+                    let quad = Quad {
+                        tl: corner(-h, -h, 0.0, 0.0),
+                        tr: corner(h, -h, 1.0, 0.0),
+                        br: corner(h, h, 1.0, 1.0),
+                        bl: corner(-h, h, 0.0, 1.0),
+                    };
+                    let first = vertices.len() as u32;
+                    vertices.extend_from_slice(&[quad.tl, quad.tr, quad.br, quad.bl]);
+                    indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+                }
+                // four vertices and six indices each, always in range
+                let _ = g.mesh(&vertices, &indices, Some(&image));
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Vector paths made of lines and bezier curves, flattened into polylines for stroking.
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum PathCommand {
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    QuadTo([f32; 2], [f32; 2]),
+    CubicTo([f32; 2], [f32; 2], [f32; 2]),
+    Close,
+}
+
+#[derive(Clone,Debug,Default)]
+pub struct Path {
+    pub commands: Vec<PathCommand>,
+}
+
+// A flattened piece of a path, drawn as one connected polyline.
+#[derive(Clone,Debug,Default)]
+pub struct SubPath {
+    pub points: Vec<[f32; 2]>,
+    pub closed: bool,
+}
+
+impl Path {
+    // Maximum distance in pixels between a curve and its flattened segments.
+    pub const TOLERANCE: f32 = 0.25;
+    // Guards against runaway subdivision on degenerate curves and tiny tolerances,
+    // a curve is never split into more than 2^MAX_DEPTH segments.
+    const MAX_DEPTH: u32 = 10;
+
+    pub fn new() -> Self {
+        Self { commands: vec![] }
+    }
+    pub fn move_to(mut self, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::MoveTo([x,y]));
+        self
+    }
+    pub fn line_to(mut self, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::LineTo([x,y]));
+        self
+    }
+    pub fn quad_to(mut self, cx: f32, cy: f32, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::QuadTo([cx,cy],[x,y]));
+        self
+    }
+    pub fn cubic_to(mut self, c1x: f32, c1y: f32, c2x: f32, c2y: f32, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::CubicTo([c1x,c1y],[c2x,c2y],[x,y]));
+        self
+    }
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    pub fn flatten(&self, tolerance: f32) -> Vec<SubPath> {
+        let mut subpaths = vec![];
+        let mut current = SubPath::default();
+        let mut start = [0.0,0.0];
+        let mut pen = [0.0,0.0];
+        for command in &self.commands {
+            match *command {
+                PathCommand::MoveTo(p) => {
+                    if current.points.len() > 1 {
+                        subpaths.push(std::mem::take(&mut current));
+                    }
+                    current.points.clear();
+                    current.points.push(p);
+                    start = p;
+                    pen = p;
+                }
+                PathCommand::LineTo(p) => {
+                    if current.points.is_empty() { current.points.push(pen); }
+                    current.points.push(p);
+                    pen = p;
+                }
+                PathCommand::QuadTo(c, p) => {
+                    if current.points.is_empty() { current.points.push(pen); }
+                    flatten_quad(pen, c, p, tolerance, &mut current.points);
+                    pen = p;
+                }
+                PathCommand::CubicTo(c1, c2, p) => {
+                    if current.points.is_empty() { current.points.push(pen); }
+                    flatten_cubic(pen, c1, c2, p, tolerance, &mut current.points);
+                    pen = p;
+                }
+                PathCommand::Close => {
+                    if current.points.len() > 1 {
+                        current.closed = true;
+                        subpaths.push(std::mem::take(&mut current));
+                    }
+                    current.points.clear();
+                    pen = start;
+                }
+            }
+        }
+        if current.points.len() > 1 {
+            subpaths.push(current);
+        }
+        subpaths
+    }
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+// Distance from p to the segment from a to b. Control points past the ends count as far from it,
+// so curves that overshoot the chord still get split.
+fn segment_distance(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 < f32::EPSILON {
+        0.0
+    } else {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / len2).clamp(0.0, 1.0)
+    };
+    ((p[0] - a[0] - dx * t).powi(2) + (p[1] - a[1] - dy * t).powi(2)).sqrt()
+}
+
+// Quadratics are elevated to cubics so both share one subdivision routine.
+pub fn flatten_quad(p0: [f32; 2], c: [f32; 2], p1: [f32; 2], tolerance: f32, out: &mut Vec<[f32; 2]>) {
+    let c1 = lerp(p0, c, 2.0 / 3.0);
+    let c2 = lerp(p1, c, 2.0 / 3.0);
+    flatten_cubic(p0, c1, c2, p1, tolerance, out);
+}
+
+// The start point is assumed to already be in out.
+pub fn flatten_cubic(p0: [f32; 2], c1: [f32; 2], c2: [f32; 2], p1: [f32; 2], tolerance: f32, out: &mut Vec<[f32; 2]>) {
+    subdivide(p0, c1, c2, p1, tolerance, 0, out);
+}
+
+// Recursively splits the curve in half until the control points are within tolerance of the chord.
+fn subdivide(p0: [f32; 2], c1: [f32; 2], c2: [f32; 2], p1: [f32; 2], tolerance: f32, depth: u32, out: &mut Vec<[f32; 2]>) {
+    let flat = segment_distance(c1, p0, p1).max(segment_distance(c2, p0, p1));
+    if flat <= tolerance || depth >= Path::MAX_DEPTH {
+        out.push(p1);
+        return;
+    }
+    let ab = lerp(p0, c1, 0.5);
+    let bc = lerp(c1, c2, 0.5);
+    let cd = lerp(c2, p1, 0.5);
+    let abc = lerp(ab, bc, 0.5);
+    let bcd = lerp(bc, cd, 0.5);
+    let mid = lerp(abc, bcd, 0.5);
+    subdivide(p0, ab, abc, mid, tolerance, depth + 1, out);
+    subdivide(mid, bcd, cd, p1, tolerance, depth + 1, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cubic(p0: [f32; 2], c1: [f32; 2], c2: [f32; 2], p1: [f32; 2], tolerance: f32) -> Vec<[f32; 2]> {
+        let mut out = vec![p0];
+        flatten_cubic(p0, c1, c2, p1, tolerance, &mut out);
+        out
+    }
+
+    fn point_at(p0: [f32; 2], c1: [f32; 2], c2: [f32; 2], p1: [f32; 2], t: f32) -> [f32; 2] {
+        let (ab, bc, cd) = (lerp(p0, c1, t), lerp(c1, c2, t), lerp(c2, p1, t));
+        lerp(lerp(ab, bc, t), lerp(bc, cd, t), t)
+    }
+
+    #[test]
+    fn straight_curve_is_one_segment() {
+        let points = cubic([0.0,0.0], [10.0,0.0], [20.0,0.0], [30.0,0.0], Path::TOLERANCE);
+        assert_eq!(points, vec![[0.0,0.0], [30.0,0.0]]);
+    }
+
+    #[test]
+    fn tight_curve_stays_within_tolerance() {
+        let (p0, c1, c2, p1) = ([0.0,0.0], [100.0,100.0], [-100.0,100.0], [0.0,0.0]);
+        let points = cubic(p0, c1, c2, p1, Path::TOLERANCE);
+        assert!(points.len() > 8);
+        assert_eq!(points.last(), Some(&p1));
+        // every point on the curve is near the polyline
+        for i in 0..=100 {
+            let p = point_at(p0, c1, c2, p1, i as f32 / 100.0);
+            let d = points.windows(2).map(|w| segment_distance(p, w[0], w[1])).fold(f32::MAX, f32::min);
+            assert!(d <= Path::TOLERANCE + 1e-3, "{:?} is {} from the polyline", p, d);
+        }
+    }
+
+    #[test]
+    fn zero_tolerance_is_bounded() {
+        let points = cubic([0.0,0.0], [0.0,50.0], [50.0,50.0], [50.0,0.0], 0.0);
+        assert!(points.len() <= (1 << Path::MAX_DEPTH) + 1);
+        assert_eq!(points.last(), Some(&[50.0,0.0]));
+    }
+
+    #[test]
+    fn quads_end_at_their_end_point() {
+        let mut out = vec![[0.0,0.0]];
+        flatten_quad([0.0,0.0], [50.0,100.0], [100.0,0.0], Path::TOLERANCE, &mut out);
+        assert!(out.len() > 2);
+        assert_eq!(out.last(), Some(&[100.0,0.0]));
+    }
+
+    #[test]
+    fn close_and_move_split_subpaths() {
+        let path = Path::new()
+            .move_to(0.0,0.0).line_to(10.0,0.0).line_to(10.0,10.0).close()
+            .move_to(20.0,0.0).line_to(30.0,0.0);
+        let subpaths = path.flatten(Path::TOLERANCE);
+        assert_eq!(subpaths.len(), 2);
+        assert!(subpaths[0].closed);
+        assert_eq!(subpaths[0].points.len(), 3);
+        assert!(!subpaths[1].closed);
+        assert_eq!(subpaths[1].points, vec![[20.0,0.0], [30.0,0.0]]);
+    }
+}
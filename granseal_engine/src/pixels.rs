@@ -0,0 +1,179 @@
+use crate::shape::Color;
+
+// Pixels of a texture made with Graphics::create_texture, four sRGB bytes each, row by row.
+// Changed pixels are uploaded before the next frame is drawn, only the rectangle around them.
+#[derive(Clone,Debug)]
+pub struct PixelBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    // x0,y0,x1,y1 in pixels, exclusive at the far end
+    dirty: Option<[u32;4]>,
+}
+
+fn byte(c: f32) -> u8 {
+    (c.clamp(0.0,1.0) * 255.0).round() as u8
+}
+
+impl PixelBuffer {
+    // Transparent black, and dirty so it all goes up the first time.
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width,height) = (width.max(1),height.max(1));
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            dirty: Some([0,0,width,height]),
+        }
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    // Where a pixel's bytes start, in usize as big buffers overflow u32.
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    // Marks part of the buffer as changed, clipped to it.
+    pub fn mark_dirty(&mut self, x: u32, y: u32, width: u32, height: u32) -> &mut Self {
+        let (x1,y1) = (x.saturating_add(width).min(self.width),y.saturating_add(height).min(self.height));
+        if x >= x1 || y >= y1 {
+            return self;
+        }
+        self.dirty = Some(match self.dirty {
+            Some([a0,b0,a1,b1]) => [a0.min(x),b0.min(y),a1.max(x1),b1.max(y1)],
+            None => [x,y,x1,y1],
+        });
+        self
+    }
+    pub fn get(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = self.offset(x,y);
+        let p = &self.pixels[i..i + 4];
+        Some(Color::new(p[0] as f32 / 255.0,p[1] as f32 / 255.0,p[2] as f32 / 255.0,p[3] as f32 / 255.0))
+    }
+    // Pixels outside the buffer are ignored.
+    pub fn set(&mut self, x: u32, y: u32, color: Color) -> &mut Self {
+        self.fill_rect(x,y,1,1,color)
+    }
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) -> &mut Self {
+        let bytes = [byte(color.r),byte(color.g),byte(color.b),byte(color.a)];
+        let (x1,y1) = (x.saturating_add(width).min(self.width),y.saturating_add(height).min(self.height));
+        for row in y.min(y1)..y1 {
+            let start = self.offset(x.min(x1),row);
+            let end = self.offset(x1,row);
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&bytes);
+            }
+        }
+        self.mark_dirty(x,y,width,height)
+    }
+    pub fn fill(&mut self, color: Color) -> &mut Self {
+        self.fill_rect(0,0,self.width,self.height,color)
+    }
+    // Copies rgba rows of the given width into the buffer at x,y, clipped to it.
+    pub fn write(&mut self, x: u32, y: u32, width: u32, rgba: &[u8]) -> &mut Self {
+        if width == 0 {
+            return self;
+        }
+        let height = (rgba.len() / (width as usize * 4)) as u32;
+        let x1 = x.saturating_add(width).min(self.width);
+        let y1 = y.saturating_add(height).min(self.height);
+        for row in y.min(y1)..y1 {
+            let from = (row - y) as usize * width as usize * 4;
+            let start = self.offset(x.min(x1),row);
+            let end = self.offset(x1,row);
+            self.pixels[start..end].copy_from_slice(&rgba[from..from + end - start]);
+        }
+        self.mark_dirty(x,y,width,height)
+    }
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+    // All of the pixels, which are all uploaded again.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        self.dirty = Some([0,0,self.width,self.height]);
+        &mut self.pixels
+    }
+    pub(crate) fn take_dirty(&mut self) -> Option<[u32;4]> {
+        self.dirty.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(width: u32, height: u32) -> PixelBuffer {
+        let mut pixels = PixelBuffer::new(width,height);
+        pixels.take_dirty();
+        pixels
+    }
+
+    #[test]
+    fn new_buffers_are_all_dirty() {
+        let mut pixels = PixelBuffer::new(0,3);
+        assert_eq!((pixels.width(),pixels.height()), (1,3));
+        assert_eq!(pixels.take_dirty(), Some([0,0,1,3]));
+        assert_eq!(pixels.take_dirty(), None);
+        assert_eq!(pixels.get(0,2), Some(Color::new(0.0,0.0,0.0,0.0)));
+    }
+
+    #[test]
+    fn fill_rect_clips_to_the_buffer() {
+        let mut pixels = clean(4,4);
+        pixels.fill_rect(2,3,10,10,Color::new(1.0,0.0,0.0,1.0));
+        assert_eq!(pixels.take_dirty(), Some([2,3,4,4]));
+        assert_eq!(pixels.get(3,3), Some(Color::new(1.0,0.0,0.0,1.0)));
+        assert_eq!(pixels.get(1,3), Some(Color::new(0.0,0.0,0.0,0.0)));
+        assert_eq!(pixels.get(2,2), Some(Color::new(0.0,0.0,0.0,0.0)));
+        assert_eq!(pixels.get(4,3), None);
+        // wholly outside, or overflowing u32
+        pixels.fill_rect(4,0,2,2,Color::new(1.0,1.0,1.0,1.0));
+        pixels.set(u32::MAX,u32::MAX,Color::new(1.0,1.0,1.0,1.0));
+        pixels.fill_rect(1,1,u32::MAX,0,Color::new(1.0,1.0,1.0,1.0));
+        assert_eq!(pixels.take_dirty(), None);
+    }
+
+    #[test]
+    fn dirty_regions_grow_to_cover_every_change() {
+        let mut pixels = clean(8,8);
+        pixels.set(1,6,Color::new(1.0,1.0,1.0,1.0));
+        pixels.set(5,2,Color::new(1.0,1.0,1.0,1.0));
+        assert_eq!(pixels.take_dirty(), Some([1,2,6,7]));
+        pixels.mark_dirty(6,6,100,1);
+        assert_eq!(pixels.take_dirty(), Some([6,6,8,7]));
+        pixels.pixels_mut()[0] = 255;
+        assert_eq!(pixels.take_dirty(), Some([0,0,8,8]));
+        assert_eq!(pixels.pixels()[0], 255);
+    }
+
+    #[test]
+    fn write_copies_rows_clipped_to_the_buffer() {
+        let mut pixels = clean(3,3);
+        let rgba: Vec<u8> = (0..2 * 2 * 4).map(|i| i as u8).collect();
+        pixels.write(2,1,2,&rgba);
+        assert_eq!(pixels.take_dirty(), Some([2,1,3,3]));
+        // each row starts at its own place in rgba, even though only its first pixel fits
+        assert_eq!(&pixels.pixels()[(3 + 2) * 4..(3 + 3) * 4], &rgba[0..4]);
+        assert_eq!(&pixels.pixels()[(6 + 2) * 4..(6 + 3) * 4], &rgba[8..12]);
+        assert_eq!(pixels.get(1,1), Some(Color::new(0.0,0.0,0.0,0.0)));
+        // a partial last row is dropped
+        pixels.write(0,0,2,&rgba[..12]);
+        assert_eq!(pixels.take_dirty(), Some([0,0,2,1]));
+        pixels.write(0,0,0,&rgba);
+        pixels.write(5,5,2,&rgba);
+        assert_eq!(pixels.take_dirty(), None);
+    }
+
+    #[test]
+    fn colors_round_to_bytes() {
+        let mut pixels = clean(1,1);
+        pixels.set(0,0,Color::new(0.5,2.0,-1.0,0.25));
+        assert_eq!(pixels.pixels(), &[128,255,0,64]);
+    }
+}
@@ -9,8 +9,19 @@ use wgpu::util::DeviceExt;
 use winit::event::WindowEvent;
 use winit::window::Window;
 
-use crate::{events, GransealGameConfig, GransealGameState, Graphics, KeyState, map_events, map_present_modes, Shape, Texture, TextureInfo};
+use crate::{events, GransealGameConfig, GransealGameState, Graphics, KeyState, map_events, map_present_modes, Shape, Texture, TextureInfo, Vertex};
 use crate::events::Event;
+use crate::paint::GradientStop;
+use crate::shape::TextureHandle;
+
+pub(crate) const SHAPE_SHADER: &str = include_str!("shape_shader.wgsl");
+const MESH_SHADER: &str = include_str!("mesh_shader.wgsl");
+// Shared by both shaders, spliced in at their // ENCODE SRGB line.
+pub(crate) const ENCODE_SRGB: &str = include_str!("srgb.wgsl");
+
+pub(crate) fn mesh_shader_source() -> String {
+    crate::kinds::splice(MESH_SHADER, "// ENCODE SRGB", ENCODE_SRGB)
+}
 
 #[derive(Copy,Clone,Debug)]
 pub enum GransealError {
@@ -37,6 +48,10 @@ impl Castle {
     pub fn clear(&mut self,value: bool) {
         self.clear = value;
     }
+    // Last cursor position in window pixels, the same space shapes are drawn in.
+    pub fn mouse(&self) -> [f64; 2] {
+        self.mouse_pos
+    }
 }
 
 
@@ -52,10 +67,24 @@ pub struct GransealEngine {
     pub(crate) game_state: Box<dyn GransealGameState>,
     graphics: Graphics,
     shape_buffer: wgpu::Buffer,
+    shape_capacity: usize,
+    // What the shape buffer holds, so only shapes that changed since the last frame get written.
+    uploaded_shapes: Vec<Shape>,
     render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
     screen_buffer: wgpu::Buffer,
     screen_bind_group: wgpu::BindGroup,
+    screen_bind_group_layout: wgpu::BindGroupLayout,
     time_buffer: wgpu::Buffer,
+    gradient_buffer: wgpu::Buffer,
+    gradient_capacity: usize,
+    polygon_buffer: wgpu::Buffer,
+    polygon_capacity: usize,
+    mesh_pipeline: wgpu::RenderPipeline,
+    mesh_vertex_buffer: wgpu::Buffer,
+    mesh_vertex_capacity: usize,
+    mesh_index_buffer: wgpu::Buffer,
+    mesh_index_capacity: usize,
     castle: Castle,
 }
 
@@ -74,9 +103,17 @@ impl GransealEngine {
             },
         ).await.ok_or(GransealError::AdapterErr)?;
 
+        // 32 bit float textures are only filterable on adapters that say so
+        let float32_filterable = adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+            && adapter.get_texture_format_features(wgpu::TextureFormat::Rgba32Float).flags
+                .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE);
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty() ,
+                features: if float32_filterable {
+                    wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                } else {
+                    wgpu::Features::empty()
+                },
                 limits: wgpu::Limits::default(),
                 label: None,
             },
@@ -98,15 +135,10 @@ impl GransealEngine {
 
         let key_down = HashMap::new();
 
-        let graphics = Graphics::new(device.clone(),queue.clone());
+        let graphics = Graphics::new(device.clone(),queue.clone(),float32_filterable);
 
-        let shape_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Shape Buffer"),
-                contents: bytemuck::cast_slice(graphics.shapes.as_slice()),
-                usage: wgpu::BufferUsages::VERTEX
-            }
-        );
+        let shape_capacity = 256;
+        let shape_buffer = Self::shape_buffer(&device, shape_capacity);
 
         let clear_color = engine_cfg.clear_color;
 
@@ -146,28 +178,34 @@ impl GransealEngine {
                     },
                     count: None
                 },
-            ],
-            label: Some("screen_bind_group_layout"),
-        });
-        let screen_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &screen_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: screen_buffer.as_entire_binding(),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
                 },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: time_buffer.as_entire_binding(),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
                 },
             ],
-            label: Some("screen_bind_group"),
-        });
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shape_shader.wgsl").into()),
+            label: Some("screen_bind_group_layout"),
         });
+        let gradient_capacity = 64 * std::mem::size_of::<GradientStop>();
+        let gradient_buffer = Self::storage_buffer(&device, "Gradient Buffer", gradient_capacity);
+        let polygon_capacity = 256 * std::mem::size_of::<[f32; 2]>();
+        let polygon_buffer = Self::storage_buffer(&device, "Polygon Buffer", polygon_capacity);
+        let screen_bind_group = Self::screen_bind_group(&device, &screen_bind_group_layout, &screen_buffer, &time_buffer, &gradient_buffer, &polygon_buffer);
 
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -175,9 +213,61 @@ impl GransealEngine {
                 bind_group_layouts: &[&screen_bind_group_layout,&graphics.texture_bind_group_layout],
                 push_constant_ranges: &[],
             });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let render_pipeline = Self::shape_pipeline(&device, &render_pipeline_layout, config.format, &graphics.shape_kinds.shader_source(SHAPE_SHADER));
+        let mesh_pipeline = Self::mesh_pipeline(&device, &render_pipeline_layout, config.format);
+        let mesh_vertex_capacity = 1024 * std::mem::size_of::<Vertex>();
+        let mesh_vertex_buffer = Self::mesh_buffer(&device, "Mesh Vertex Buffer", wgpu::BufferUsages::VERTEX, mesh_vertex_capacity);
+        let mesh_index_capacity = 1024 * std::mem::size_of::<u32>();
+        let mesh_index_buffer = Self::mesh_buffer(&device, "Mesh Index Buffer", wgpu::BufferUsages::INDEX, mesh_index_capacity);
+
+        let castle = Castle {
+            key_down,
+            mouse_pos,
+            clear_color,
+            timer,
+            clear: true,
+        };
+
+        Ok(GransealEngine {
+            window,
+            engine_cfg,
+            surface,
+            device,
+            queue,
+            surface_cfg: config,
+            size,
+            game_state,
+            graphics,
+            shape_buffer,
+            shape_capacity,
+            uploaded_shapes: vec![],
+            render_pipeline,
+            render_pipeline_layout,
+            screen_buffer,
+            screen_bind_group,
+            screen_bind_group_layout,
+            time_buffer,
+            gradient_buffer,
+            gradient_capacity,
+            polygon_buffer,
+            polygon_capacity,
+            mesh_pipeline,
+            mesh_vertex_buffer,
+            mesh_vertex_capacity,
+            mesh_index_buffer,
+            mesh_index_capacity,
+            castle,
+        })
+    }
+
+    fn shape_pipeline(device: &wgpu::Device, layout: &wgpu::PipelineLayout, format: wgpu::TextureFormat, source: &str) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Shape Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
@@ -187,7 +277,7 @@ impl GransealEngine {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -196,7 +286,7 @@ impl GransealEngine {
                 topology: wgpu::PrimitiveTopology::TriangleStrip,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                cull_mode: None, // mirroring transforms flip the winding
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -204,35 +294,180 @@ impl GransealEngine {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
+        })
+    }
+
+    fn mesh_pipeline(device: &wgpu::Device, layout: &wgpu::PipelineLayout, format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(mesh_shader_source().into()),
         });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
 
-        let castle = Castle {
-            key_down,
-            mouse_pos,
-            clear_color,
-            timer,
-            clear: true,
-        };
+    // Recompiles the shape shader after custom kinds are registered. A shader that fails
+    // to compile is reported and the previous pipeline stays in use.
+    fn rebuild_shape_pipeline(&mut self) {
+        self.graphics.shape_kinds.changed = false;
+        let source = self.graphics.shape_kinds.shader_source(SHAPE_SHADER);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = Self::shape_pipeline(&self.device, &self.render_pipeline_layout, self.surface_cfg.format, &source);
+        match pollster::block_on(self.device.pop_error_scope()) {
+            None => self.render_pipeline = pipeline,
+            Some(e) => eprintln!("Error compiling custom shape kinds: {}", e),
+        }
+    }
 
-        Ok(GransealEngine {
-            window,
-            engine_cfg,
-            surface,
-            device,
-            queue,
-            surface_cfg: config,
-            size,
-            game_state,
-            graphics,
-            shape_buffer,
-            render_pipeline,
-            screen_buffer,
-            screen_bind_group,
-            time_buffer,
-            castle,
+    fn shape_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shape Buffer"),
+            size: (capacity * std::mem::size_of::<Shape>()) as BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         })
     }
 
+    // Writes the runs of shapes that differ from last frame, a mostly still scene uploads very little.
+    fn upload_shapes(&mut self) {
+        let shapes = &self.graphics.shapes;
+        if shapes.len() > self.shape_capacity {
+            self.shape_capacity = shapes.len().next_power_of_two();
+            self.shape_buffer.destroy();
+            self.shape_buffer = Self::shape_buffer(&self.device, self.shape_capacity);
+            self.uploaded_shapes.clear();
+        }
+        let unchanged = |i: usize| self.uploaded_shapes.get(i)
+            .is_some_and(|old| bytemuck::bytes_of(old) == bytemuck::bytes_of(&shapes[i]));
+        let mut i = 0;
+        while i < shapes.len() {
+            if unchanged(i) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < shapes.len() && !unchanged(i) {
+                i += 1;
+            }
+            let offset = (start * std::mem::size_of::<Shape>()) as BufferAddress;
+            self.queue.write_buffer(&self.shape_buffer, offset, bytemuck::cast_slice(&shapes[start..i]));
+        }
+        self.uploaded_shapes.clear();
+        self.uploaded_shapes.extend_from_slice(shapes);
+    }
+
+    fn mesh_buffer(device: &wgpu::Device, label: &str, usage: wgpu::BufferUsages, size: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size as BufferAddress,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn upload_meshes(&mut self) {
+        let vertices = bytemuck::cast_slice::<Vertex, u8>(&self.graphics.mesh_vertices);
+        let indices = bytemuck::cast_slice::<u32, u8>(&self.graphics.mesh_indices);
+        if vertices.len() > self.mesh_vertex_capacity {
+            self.mesh_vertex_capacity = vertices.len().next_power_of_two();
+            self.mesh_vertex_buffer.destroy();
+            self.mesh_vertex_buffer = Self::mesh_buffer(&self.device, "Mesh Vertex Buffer", wgpu::BufferUsages::VERTEX, self.mesh_vertex_capacity);
+        }
+        if indices.len() > self.mesh_index_capacity {
+            self.mesh_index_capacity = indices.len().next_power_of_two();
+            self.mesh_index_buffer.destroy();
+            self.mesh_index_buffer = Self::mesh_buffer(&self.device, "Mesh Index Buffer", wgpu::BufferUsages::INDEX, self.mesh_index_capacity);
+        }
+        self.queue.write_buffer(&self.mesh_vertex_buffer, 0, vertices);
+        self.queue.write_buffer(&self.mesh_index_buffer, 0, indices);
+    }
+
+    fn storage_buffer(device: &wgpu::Device, label: &str, size: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size as BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn screen_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, screen: &wgpu::Buffer, time: &wgpu::Buffer, gradients: &wgpu::Buffer, polygons: &wgpu::Buffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: screen.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: time.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: gradients.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: polygons.as_entire_binding(),
+                },
+            ],
+            label: Some("screen_bind_group"),
+        })
+    }
+
+    // Storage buffers grow to fit the frame's gradient stops and polygon points, which means a new bind group.
+    fn upload_storage(&mut self) {
+        let stops = bytemuck::cast_slice::<GradientStop, u8>(&self.graphics.gradient_stops);
+        let points = bytemuck::cast_slice::<[f32; 2], u8>(&self.graphics.polygon_points);
+        let mut rebind = false;
+        if stops.len() > self.gradient_capacity {
+            self.gradient_capacity = stops.len().next_power_of_two();
+            self.gradient_buffer.destroy();
+            self.gradient_buffer = Self::storage_buffer(&self.device, "Gradient Buffer", self.gradient_capacity);
+            rebind = true;
+        }
+        if points.len() > self.polygon_capacity {
+            self.polygon_capacity = points.len().next_power_of_two();
+            self.polygon_buffer.destroy();
+            self.polygon_buffer = Self::storage_buffer(&self.device, "Polygon Buffer", self.polygon_capacity);
+            rebind = true;
+        }
+        if rebind {
+            self.screen_bind_group = Self::screen_bind_group(&self.device, &self.screen_bind_group_layout, &self.screen_buffer, &self.time_buffer, &self.gradient_buffer, &self.polygon_buffer);
+        }
+        self.queue.write_buffer(&self.gradient_buffer, 0, stops);
+        self.queue.write_buffer(&self.polygon_buffer, 0, points);
+    }
+
 
 
     pub(crate) fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -247,21 +482,27 @@ impl GransealEngine {
     }
 
     pub(crate) fn input(&mut self, event: &WindowEvent) -> Result<bool,GransealError> {
-        let granseal_event = map_events(event);
+        let mut granseal_event = map_events(event);
         if granseal_event.is_some() {
-            match granseal_event.ok_or(GransealError::EventError)? {
+            match granseal_event.as_mut().ok_or(GransealError::EventError)? {
                 events::Event::KeyEvent {
                     state, key, modifiers: _modifiers
                 } => {
                     match state {
                         KeyState::Pressed => {
-                            self.castle.key_down.insert(key,true);
+                            self.castle.key_down.insert(*key,true);
                         },
                         KeyState::Released => {
-                            self.castle.key_down.insert(key,false);
+                            self.castle.key_down.insert(*key,false);
                         }
                     }
                 }
+                events::Event::MouseMoved { position } => {
+                    self.castle.mouse_pos = *position;
+                }
+                events::Event::MouseButton { position, .. } => {
+                    *position = self.castle.mouse_pos;
+                }
                 _ => {}
             }
             if self.event(granseal_event.ok_or(GransealError::EventError)?) {
@@ -272,20 +513,22 @@ impl GransealEngine {
     }
 
     pub(crate) fn update(&mut self, delta_time: Duration) {
+        self.graphics.upload_loaded();
+        for path in self.graphics.reload_changed() {
+            self.event(Event::Reloaded(path));
+        }
         self.event(Event::Update(delta_time));
     }
 
     pub(crate) fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         self.event(Event::Draw);
-        let shape_buffer = self.device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Shape Buffer"),
-                contents: bytemuck::cast_slice(self.graphics.shapes.as_slice()),
-                usage: wgpu::BufferUsages::VERTEX
-            }
-        );
-        self.shape_buffer.destroy();
-        self.shape_buffer = shape_buffer;
+        self.graphics.upload_pixels();
+        if self.graphics.shape_kinds.changed {
+            self.rebuild_shape_pipeline();
+        }
+        self.upload_shapes();
+        self.upload_storage();
+        self.upload_meshes();
 
         self.queue.write_buffer(&self.time_buffer, 0, &self.castle.timer.elapsed().as_secs_f32().to_ne_bytes().as_slice());
 
@@ -318,23 +561,52 @@ impl GransealEngine {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_vertex_buffer(0,self.shape_buffer.slice(..));
             render_pass.set_bind_group(0,&self.screen_bind_group,&[]);
-
-
-            for (i, _) in self.graphics.shapes.iter_mut().enumerate() {
-                let tex = if self.graphics.images.contains_key(&i) {
-                    self.graphics.images.index(&i)
-                } else {Graphics::ERROR_IMG};
-                let t = self.graphics.textures.get(tex);
-                match t {
-                    Some(x) => {
-                        render_pass.set_bind_group(1,&x.bind_group, &[]);
-                    },
-                    None => {
-                        let path = std::env::current_dir().expect("Couldn't get the current directory.");
-                        println!("Couldn't find texture: {} in path: {:?}",tex,path);
-                    },
+            render_pass.set_index_buffer(self.mesh_index_buffer.slice(..),wgpu::IndexFormat::Uint32);
+
+            // meshes go between the shapes drawn before and after them, switching pipelines as needed
+            let mut meshes = self.graphics.meshes.iter().peekable();
+            let mut bound: Option<TextureHandle> = None;
+            // textures unloaded after their draw was queued show the error image, like stale handles
+            let error = self.graphics.error_texture();
+            let textures = &self.graphics.textures;
+            let resolve = |handle: TextureHandle| match textures.get(handle) {
+                Some(texture) => (handle,texture),
+                None => (error,textures.get(error).expect("The error texture is always loaded.")),
+            };
+            let images = &self.graphics.images;
+            let texture_of = |i: usize| resolve(images.get(&i).copied().unwrap_or(error));
+            let count = self.graphics.shapes.len();
+            let mut i = 0;
+            loop {
+                if meshes.peek().is_some_and(|m| m.at == i) {
+                    render_pass.set_pipeline(&self.mesh_pipeline);
+                    render_pass.set_vertex_buffer(0,self.mesh_vertex_buffer.slice(..));
+                    while let Some(mesh) = meshes.next_if(|m| m.at == i) {
+                        let (handle,texture) = resolve(mesh.image);
+                        if bound != Some(handle) {
+                            render_pass.set_bind_group(1,&texture.bind_group,&[]);
+                            bound = Some(handle);
+                        }
+                        render_pass.draw_indexed(mesh.first_index..mesh.first_index + mesh.count,mesh.base_vertex,0..1);
+                    }
+                    render_pass.set_pipeline(&self.render_pipeline);
+                    render_pass.set_vertex_buffer(0,self.shape_buffer.slice(..));
+                }
+                if i == count {
+                    break;
+                }
+                let (handle,texture) = texture_of(i);
+                if bound != Some(handle) {
+                    render_pass.set_bind_group(1,&texture.bind_group, &[]);
+                    bound = Some(handle);
+                }
+                // shapes after it with the same texture, like images on one atlas page, go in one draw
+                let mut end = i + 1;
+                while end < count && !matches!(meshes.peek(), Some(m) if m.at == end) && texture_of(end).0 == handle {
+                    end += 1;
                 }
-                render_pass.draw(0..5 as u32,i as u32..(i+1) as u32);
+                render_pass.draw(0..4,i as u32..end as u32);
+                i = end;
             }
         }
         self.queue.submit(std::iter::once(encoder.finish()));
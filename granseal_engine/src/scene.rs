@@ -0,0 +1,400 @@
+use crate::shape::{Color, Graphics, ShapeKind};
+use crate::transform::Transform;
+
+// What a node draws, in its own coordinates with the top left at 0,0.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Drawable {
+    // Draws nothing, only moves its children.
+    Group,
+    Rect { width: f32, height: f32 },
+    FillRect { width: f32, height: f32 },
+    Oval { width: f32, height: f32 },
+    FillOval { width: f32, height: f32 },
+    RoundRect { width: f32, height: f32, radius: f32 },
+    FillRoundRect { width: f32, height: f32, radius: f32 },
+    Line { to: [f32; 2] },
+    Polygon(Vec<[f32; 2]>),
+    FillPolygon(Vec<[f32; 2]>),
+    Image(String),
+    // Any kind, like the ones registered with Graphics::register_shape.
+    Shape { kind: ShapeKind, width: f32, height: f32 },
+}
+
+#[derive(Clone,Debug)]
+pub struct Node {
+    pub drawable: Drawable,
+    pub position: [f32; 2],
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    // rotation and scale happen around this point, in the node's coordinates
+    pub origin: [f32; 2],
+    // Siblings draw in order of z, then in the order they were added.
+    // Children with a negative z draw behind their parent.
+    pub z: i32,
+    // Hidden nodes hide their children too.
+    pub visible: bool,
+    pub color: Color,
+    // Outlines filled drawables when set.
+    pub outline: Option<Color>,
+    pub thickness: f32,
+    // Given to Graphics::tag for the node's shapes, see Graphics::hit_tag.
+    pub tag: Option<u64>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    pub fn new(drawable: Drawable) -> Self {
+        Self {
+            drawable,
+            position: [0.0,0.0],
+            rotation: 0.0,
+            scale: [1.0,1.0],
+            origin: [0.0,0.0],
+            z: 0,
+            visible: true,
+            color: Color::WHITE,
+            outline: None,
+            thickness: 1.0,
+            tag: None,
+            parent: None,
+            children: vec![],
+        }
+    }
+    pub fn group() -> Self {
+        Self::new(Drawable::Group)
+    }
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.position = [x,y];
+        self
+    }
+    pub fn rotation(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+    pub fn scale(mut self, sx: f32, sy: f32) -> Self {
+        self.scale = [sx,sy];
+        self
+    }
+    pub fn origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = [x,y];
+        self
+    }
+    pub fn z(mut self, z: i32) -> Self {
+        self.z = z;
+        self
+    }
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn outline(mut self, color: Option<Color>) -> Self {
+        self.outline = color;
+        self
+    }
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+    pub fn tag(mut self, tag: Option<u64>) -> Self {
+        self.tag = tag;
+        self
+    }
+    // From the node's coordinates to its parent's.
+    pub fn local_transform(&self) -> Transform {
+        Transform::translation(self.position[0],self.position[1]).multiply(&Transform::around(
+            &Transform::rotation(self.rotation).multiply(&Transform::scaling(self.scale[0],self.scale[1])),
+            self.origin,
+        ))
+    }
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+    fn draw(&self, g: &mut Graphics) {
+        g.tag(self.tag);
+        g.color(self.color);
+        g.outline(self.outline.is_some());
+        if let Some(color) = self.outline {
+            g.outline_color(color);
+        }
+        g.outline_thickness(self.thickness);
+        match &self.drawable {
+            Drawable::Group => {}
+            Drawable::Rect { width, height } => {g.rect(0.0,0.0,*width,*height);}
+            Drawable::FillRect { width, height } => {g.fill_rect(0.0,0.0,*width,*height);}
+            Drawable::Oval { width, height } => {g.oval(0.0,0.0,*width,*height);}
+            Drawable::FillOval { width, height } => {g.fill_oval(0.0,0.0,*width,*height);}
+            Drawable::RoundRect { width, height, radius } => {g.round_rect(0.0,0.0,*width,*height,*radius);}
+            Drawable::FillRoundRect { width, height, radius } => {g.fill_round_rect(0.0,0.0,*width,*height,*radius);}
+            Drawable::Line { to } => {g.line(0.0,0.0,to[0],to[1]);}
+            Drawable::Polygon(points) => {g.polygon(points);}
+            Drawable::FillPolygon(points) => {g.fill_polygon(points);}
+            Drawable::Image(path) => {g.image(path,0.0,0.0);}
+            Drawable::Shape { kind, width, height } => {g.shape(*kind,0.0,0.0,*width,*height);}
+        }
+    }
+}
+
+// Nodes are looked up by id, ids of removed nodes never come back to life.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+// Retained nodes with parents, flattened into Graphics shapes by draw.
+// Graphics is still immediate mode, the renderer only uploads the shapes that changed.
+#[derive(Clone,Debug,Default)]
+pub struct Scene {
+    slots: Vec<(u32, Option<Node>)>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self { slots: vec![], free: vec![], roots: vec![] }
+    }
+    fn insert(&mut self, node: Node) -> NodeId {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.1 = Some(node);
+                NodeId { index, generation: slot.0 }
+            }
+            None => {
+                self.slots.push((0,Some(node)));
+                NodeId { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+    pub fn add(&mut self, node: Node) -> NodeId {
+        let id = self.insert(Node { parent: None, children: vec![], ..node });
+        self.roots.push(id);
+        id
+    }
+    // None when the parent is gone.
+    pub fn add_child(&mut self, parent: NodeId, node: Node) -> Option<NodeId> {
+        self.get(parent)?;
+        let id = self.insert(Node { parent: Some(parent), children: vec![], ..node });
+        self.get_mut(parent)?.children.push(id);
+        Some(id)
+    }
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        match self.slots.get(id.index) {
+            Some((generation, node)) if *generation == id.generation => node.as_ref(),
+            _ => None,
+        }
+    }
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        match self.slots.get_mut(id.index) {
+            Some((generation, node)) if *generation == id.generation => node.as_mut(),
+            _ => None,
+        }
+    }
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+    fn unlink(&mut self, id: NodeId) {
+        let parent = self.get(id).and_then(|n| n.parent);
+        let siblings = match parent.and_then(|p| self.get_mut(p)) {
+            Some(parent) => &mut parent.children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|c| *c != id);
+    }
+    // Removes the node and everything under it.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        self.get(id)?;
+        self.unlink(id);
+        let (generation, node) = &mut self.slots[id.index];
+        *generation += 1;
+        let node = node.take()?;
+        self.free.push(id.index);
+        for child in &node.children {
+            if let Some(child) = self.get_mut(*child) {
+                child.parent = None;
+            }
+            self.remove(*child);
+        }
+        Some(node)
+    }
+    // Moves a node under a new parent, or to the top level with None. Its local transform is kept,
+    // so it jumps if the parents are placed differently. Fails rather than making a cycle.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        if let Some(parent) = parent {
+            if !self.contains(parent) || self.ancestors(parent).any(|a| a == id) {
+                return false;
+            }
+        }
+        self.unlink(id);
+        self.get_mut(id).unwrap().parent = parent;
+        match parent {
+            Some(parent) => self.get_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+        true
+    }
+    // The node itself, then its parent and so on up.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.get(id).map(|_| id), move |id| self.get(*id).and_then(|n| n.parent))
+    }
+    // From the node's coordinates to the scene's.
+    pub fn world_transform(&self, id: NodeId) -> Transform {
+        self.ancestors(id)
+            .fold(Transform::IDENTITY, |t, a| self.get(a).unwrap().local_transform().multiply(&t))
+    }
+    // Visible when it and all its ancestors are.
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        self.contains(id) && self.ancestors(id).all(|a| self.get(a).unwrap().visible)
+    }
+    fn sorted(&self, ids: &[NodeId]) -> Vec<NodeId> {
+        let mut ids = ids.to_vec();
+        ids.sort_by_key(|id| self.get(*id).map_or(0, |n| n.z));
+        ids
+    }
+    // Adds the visible nodes to g's shapes, under g's current transform. The fill and outline
+    // settings of g are put back afterwards, the tag is left at None.
+    pub fn draw(&self, g: &mut Graphics) {
+        let (color, gradient) = (g.fill_color, g.fill_gradient.clone());
+        let (outline, outline_color, thickness) = (g.outline, g.outline_color, g.outline_thickness);
+        // nodes have no shadow or dash of their own, so the caller's aren't applied to them
+        let (shadow, dash) = (g.shadow.take(), g.outline_dash.take());
+        for id in self.sorted(&self.roots) {
+            self.draw_node(id, g);
+        }
+        g.tag(None);
+        g.fill_color = color;
+        g.fill_gradient = gradient;
+        g.outline = outline;
+        g.outline_color = outline_color;
+        g.outline_thickness = thickness;
+        g.shadow = shadow;
+        g.outline_dash = dash;
+    }
+    fn draw_node(&self, id: NodeId, g: &mut Graphics) {
+        let node = match self.get(id) {
+            Some(node) if node.visible => node,
+            _ => return,
+        };
+        g.push_transform();
+        g.set_transform(g.transform.multiply(&node.local_transform()));
+        g.set_origin(0.0,0.0);
+        let children = self.sorted(&node.children);
+        let behind = children.iter().take_while(|c| self.get(**c).is_some_and(|n| n.z < 0)).count();
+        for child in &children[..behind] {
+            self.draw_node(*child, g);
+        }
+        node.draw(g);
+        for child in &children[behind..] {
+            self.draw_node(*child, g);
+        }
+        g.pop_transform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect() -> Node {
+        Node::new(Drawable::FillRect { width: 10.0, height: 10.0 })
+    }
+
+    #[test]
+    fn remove_takes_the_subtree() {
+        let mut scene = Scene::new();
+        let root = scene.add(Node::group());
+        let child = scene.add_child(root, rect()).unwrap();
+        let grandchild = scene.add_child(child, rect()).unwrap();
+        let other = scene.add(rect());
+
+        assert!(scene.remove(child).is_some());
+        assert!(!scene.contains(child));
+        assert!(!scene.contains(grandchild));
+        assert!(scene.get(root).unwrap().children().is_empty());
+        assert_eq!(scene.roots(), &[root, other]);
+        assert!(scene.remove(child).is_none());
+        assert_eq!(scene.add_child(child, rect()), None);
+    }
+
+    #[test]
+    fn removed_ids_stay_dead_when_slots_are_reused() {
+        let mut scene = Scene::new();
+        let old = scene.add(rect());
+        scene.remove(old);
+        let new = scene.add(rect().z(3));
+        assert_ne!(old, new);
+        assert!(!scene.contains(old));
+        assert!(scene.get_mut(old).is_none());
+        assert_eq!(scene.get(new).unwrap().z, 3);
+    }
+
+    #[test]
+    fn set_parent_moves_nodes() {
+        let mut scene = Scene::new();
+        let a = scene.add(Node::group());
+        let b = scene.add(Node::group());
+        let node = scene.add_child(a, rect()).unwrap();
+
+        assert!(scene.set_parent(node, Some(b)));
+        assert!(scene.get(a).unwrap().children().is_empty());
+        assert_eq!(scene.get(b).unwrap().children(), &[node]);
+        assert_eq!(scene.get(node).unwrap().parent(), Some(b));
+
+        assert!(scene.set_parent(node, None));
+        assert_eq!(scene.roots(), &[a, b, node]);
+        assert_eq!(scene.get(node).unwrap().parent(), None);
+    }
+
+    #[test]
+    fn set_parent_refuses_cycles_and_dead_nodes() {
+        let mut scene = Scene::new();
+        let a = scene.add(Node::group());
+        let b = scene.add_child(a, Node::group()).unwrap();
+        let c = scene.add_child(b, Node::group()).unwrap();
+        assert!(!scene.set_parent(a, Some(c)));
+        assert!(!scene.set_parent(a, Some(a)));
+        assert_eq!(scene.ancestors(c).collect::<Vec<_>>(), vec![c, b, a]);
+
+        let dead = scene.add(Node::group());
+        scene.remove(dead);
+        assert!(!scene.set_parent(dead, Some(a)));
+        assert!(!scene.set_parent(c, Some(dead)));
+        assert_eq!(scene.get(c).unwrap().parent(), Some(b));
+    }
+
+    #[test]
+    fn world_transforms_and_visibility_come_from_ancestors() {
+        let mut scene = Scene::new();
+        let parent = scene.add(Node::group().at(10.0,20.0).scale(2.0,2.0));
+        let child = scene.add_child(parent, rect().at(5.0,0.0)).unwrap();
+        assert_eq!(scene.world_transform(child).apply([1.0,1.0]), [22.0,22.0]);
+
+        assert!(scene.is_visible(child));
+        scene.get_mut(parent).unwrap().visible = false;
+        assert!(!scene.is_visible(child));
+    }
+
+    #[test]
+    fn siblings_sort_by_z_then_order() {
+        let mut scene = Scene::new();
+        let a = scene.add(rect().z(1));
+        let b = scene.add(rect().z(-1));
+        let c = scene.add(rect().z(1));
+        assert_eq!(scene.sorted(scene.roots()), vec![b, a, c]);
+    }
+}
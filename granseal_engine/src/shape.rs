@@ -4,13 +4,17 @@ use std::path::Path;
 use std::rc::Rc;
 use image::{DynamicImage, GenericImage, Rgba};
 use wgpu::TextureView;
+use crate::paint::{Dash, Gradient, GradientStop, Shadow, TextureFormat, TextureOptions, Tiling, Wrap, PAINT_SOLID};
+use crate::transform::Transform;
+use crate::kinds::{InvalidShape, ShapeFunction, ShapeRegistry};
+use crate::sprites::{Grid, SpriteData, SpriteSheet};
 
-#[derive(Copy,Clone,Debug)]
+#[derive(Copy,Clone,Debug,PartialEq)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
     pub b: f32,
-    a: f32,
+    pub(crate) a: f32,
 }
 
 impl Color {
@@ -49,13 +53,7 @@ impl Color {
     pub const NAVY: Self = Self::rgb(0.0,0.0,0.5);
 }
 
-pub type ShapeKind = i32;
-pub const FILL_RECT: ShapeKind = 0;
-pub const FILL_OVAL: ShapeKind = 1;
-pub const RECT:  ShapeKind = 2;
-pub const OVAL: ShapeKind = 3;
-pub const TEX_RECT: ShapeKind = 4;
-pub const TEX_OVAL: ShapeKind = 5;
+pub use crate::kinds::{KindId, ShapeKind};
 
 #[repr(C)]
 #[derive(Copy,Clone,Debug,bytemuck::Pod,bytemuck::Zeroable)]
@@ -70,6 +68,9 @@ pub struct Vertex {
     pub alpha: f32,
 }
 impl Vertex {
+    pub fn new(x: f32, y: f32, u: f32, v: f32, color: Color) -> Self {
+        Self { x, y, u, v, red: color.r, green: color.g, blue: color.b, alpha: color.a }
+    }
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
@@ -103,6 +104,52 @@ pub struct Quad {
     pub bl: Vertex,
 }
 
+// Where an image is, its own texture or a region of an atlas page.
+struct TextureRegion {
+    handle: TextureHandle,
+    width: f32,
+    height: f32,
+    uv: [f32; 4],
+}
+
+impl TextureRegion {
+    // uv bounds of src, in pixels of the image.
+    fn sub(&self, src: Rect) -> [f32; 4] {
+        let (du, dv) = ((self.uv[2] - self.uv[0]) / self.width, (self.uv[3] - self.uv[1]) / self.height);
+        [
+            self.uv[0] + src.x * du,
+            self.uv[1] + src.y * dv,
+            self.uv[0] + (src.x + src.width) * du,
+            self.uv[1] + (src.y + src.height) * dv,
+        ]
+    }
+}
+
+// A mesh index past the end of its vertices, Graphics::mesh draws nothing then.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct InvalidMesh {
+    pub index: u32,
+    pub vertices: usize,
+}
+
+impl std::fmt::Display for InvalidMesh {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mesh index {} is out of range of its {} vertices", self.index, self.vertices)
+    }
+}
+
+impl std::error::Error for InvalidMesh {}
+
+// A run of triangles from Graphics::mesh, drawn before the shape at index at.
+#[derive(Clone,Debug)]
+pub(crate) struct MeshDraw {
+    pub(crate) at: usize,
+    pub(crate) base_vertex: i32,
+    pub(crate) first_index: u32,
+    pub(crate) count: u32,
+    pub(crate) image: TextureHandle,
+}
+
 
 #[repr(C)]
 #[derive(Copy,Clone,Debug,bytemuck::Pod,bytemuck::Zeroable)]
@@ -116,25 +163,118 @@ pub struct Shape {
     pub blue: f32,
     pub alpha: f32,
     pub angle: f32,
-    pub kind: ShapeKind,
+    pub kind: KindId,
     pub thickness: f32,
+    // kind specific
+    // arcs: start angle, end angle, inner radius (0..1), unused
+    // round rects: corner radius, unused, unused, unused
+    // shadows: blur, spread, corner radius, unused
+    // nine slices: left, top, right, bottom insets
+    pub params: [f32; 4],
+    // paint mode, first gradient stop, stop count, unused
+    pub paint: [i32; 4],
+    // see Gradient::encode
+    pub gradient: [f32; 4],
+    // polygons: first point, point count; shadows: kind of the shape casting it, then its first point and count
+    // round dash caps in z, texture wrap mode in w
+    pub data: [i32; 4],
+    // dash length, gap length, offset, length of the stroke before this shape
+    // shadows: params of the shape casting it
+    pub dash: [f32; 4],
+    // 2x2 part of the Graphics transform, applied around the center after angle
+    pub transform: [f32; 4],
+    // part of the texture textured kinds show, top left and bottom right in uv coordinates
+    pub uv: [f32; 4],
+    // texture coordinate offset in xy and scale in zw, applied before wrapping into the uv region
+    pub tiling: [f32; 4],
 }
 
 impl Shape {
     pub fn new(x: f32, y: f32, width: f32, height: f32, red: f32, green: f32, blue: f32, alpha: f32, angle: f32, kind: ShapeKind, thickness: f32) -> Self {
-        Self { x, y, width, height, red, green, blue, alpha, angle, kind , thickness }
+        Self { x, y, width, height, red, green, blue, alpha, angle, kind: kind.id(), thickness,
+            params: [0.0, std::f32::consts::TAU, 0.0, 0.0],
+            paint: [PAINT_SOLID, 0, 0, 0],
+            gradient: [0.0; 4],
+            data: [0; 4],
+            dash: [0.0; 4],
+            transform: [1.0, 0.0, 0.0, 1.0],
+            uv: [0.0, 0.0, 1.0, 1.0],
+            tiling: [0.0, 0.0, 1.0, 1.0],
+        }
     }
     pub fn fill_rect(x: f32, y: f32, w: f32, h: f32) -> Self {
-        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0,FILL_RECT, 4.0)
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0,ShapeKind::FillRect, 4.0)
     }
     pub fn fill_oval(x: f32, y: f32, w: f32, h: f32) -> Self {
-        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0,0.0,  FILL_OVAL,4.0)
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0,0.0,  ShapeKind::FillOval,4.0)
     }
     pub fn rect(x: f32, y: f32, w: f32, h: f32) -> Self {
-        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, RECT,4.0)
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Rect,4.0)
     }
     pub fn oval(x: f32, y: f32, w: f32, h: f32) -> Self {
-        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0,OVAL,4.0)
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0,ShapeKind::Oval,4.0)
+    }
+    pub fn arc(x: f32, y: f32, w: f32, h: f32, start: f32, end: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Arc, 4.0).angles(start, end)
+    }
+    pub fn fill_pie(x: f32, y: f32, w: f32, h: f32, start: f32, end: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::FillPie, 4.0).angles(start, end)
+    }
+    pub fn pie(x: f32, y: f32, w: f32, h: f32, start: f32, end: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Pie, 4.0).angles(start, end)
+    }
+    pub fn fill_ring(x: f32, y: f32, w: f32, h: f32, inner: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::FillRing, 4.0).inner(inner)
+    }
+    pub fn ring(x: f32, y: f32, w: f32, h: f32, inner: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Ring, 4.0).inner(inner)
+    }
+    // A round capped segment between two points, the quad is padded by half the thickness at each end.
+    pub fn line(x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32) -> Self {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length = (dx * dx + dy * dy).sqrt() + thickness;
+        let (cx, cy) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+        Self::new(cx - length / 2.0, cy - thickness / 2.0, length, thickness, 1.0, 1.0, 1.0, 1.0, dy.atan2(dx), ShapeKind::Line, thickness)
+    }
+    // Points are stored by Graphics, relative to the center of the bounds.
+    pub fn fill_polygon(x: f32, y: f32, w: f32, h: f32, first: i32, count: i32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::FillPolygon, 4.0).points(first, count)
+    }
+    pub fn polygon(x: f32, y: f32, w: f32, h: f32, first: i32, count: i32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Polygon, 4.0).points(first, count)
+    }
+    // Bounds include half the thickness around the points.
+    pub fn polyline(x: f32, y: f32, w: f32, h: f32, first: i32, count: i32, thickness: f32) -> Self {
+        let half = thickness / 2.0;
+        Self::new(x - half, y - half, w + thickness, h + thickness, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::Polyline, thickness).points(first, count)
+    }
+    pub fn fill_round_rect(x: f32, y: f32, w: f32, h: f32, radius: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::FillRoundRect, 4.0).corner_radius(radius)
+    }
+    pub fn round_rect(x: f32, y: f32, w: f32, h: f32, radius: f32) -> Self {
+        Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::RoundRect, 4.0).corner_radius(radius)
+    }
+    // insets are the texture pixels kept unstretched at each side.
+    pub fn nine_slice(x: f32, y: f32, w: f32, h: f32, insets: Insets) -> Self {
+        let mut shape = Self::new(x, y, w, h, 1.0, 1.0, 1.0, 1.0, 0.0, ShapeKind::NineSlice, 0.0);
+        shape.params = [insets.left, insets.top, insets.right, insets.bottom];
+        shape
+    }
+    // None when the source's kind isn't one.
+    pub fn shadow(source: &Shape, shadow: &Shadow) -> Option<Self> {
+        let radius = match ShapeKind::try_from(source.kind).ok()? {
+            ShapeKind::FillRoundRect | ShapeKind::RoundRect => source.params[0],
+            _ => 0.0,
+        };
+        let mut shape = Self::new(
+            source.x + shadow.offset[0], source.y + shadow.offset[1], source.width, source.height,
+            1.0, 1.0, 1.0, 1.0, source.angle, ShapeKind::Shadow, 0.0
+        ).color(shadow.color).opacity(shadow.color.a);
+        shape.params = [shadow.blur, shadow.spread, radius, 0.0];
+        shape.data = [source.kind.code(), source.data[0], source.data[1], 0];
+        shape.dash = source.params;
+        shape.transform = source.transform;
+        Some(shape)
     }
     pub fn rgb(mut self, r: f32, g: f32, b: f32) -> Self {
         self.red = r;
@@ -153,17 +293,68 @@ impl Shape {
         self
     }
     pub fn kind(mut self, k: ShapeKind) -> Self {
-        self.kind = k;
+        self.kind = k.id();
         self
     }
     pub fn angle(mut self, a: f32) -> Self {
         self.angle = a;
         self
     }
+    pub fn transform(mut self, m: [f32; 4]) -> Self {
+        self.transform = m;
+        self
+    }
+    pub fn uv(mut self, u0: f32, v0: f32, u1: f32, v1: f32) -> Self {
+        self.uv = [u0, v0, u1, v1];
+        self
+    }
+    pub fn tiling(mut self, tiling: [f32; 4], wrap: Wrap) -> Self {
+        self.tiling = tiling;
+        self.data[3] = wrap.code();
+        self
+    }
     pub fn thickness(mut self, t: f32) -> Self {
         self.thickness = t;
         self
     }
+    // Angles in radians, clockwise from the positive x axis. Sweeps of a full turn or more draw the whole shape.
+    pub fn angles(mut self, start: f32, end: f32) -> Self {
+        if end < start {
+            self.params[0] = end;
+            self.params[1] = start;
+        } else {
+            self.params[0] = start;
+            self.params[1] = end;
+        }
+        self
+    }
+    pub fn points(mut self, first: i32, count: i32) -> Self {
+        self.data[0] = first;
+        self.data[1] = count;
+        self
+    }
+    // start is how far along a connected stroke this shape begins, keeping polylines continuous.
+    pub fn dash(mut self, dash: &Dash, start: f32) -> Self {
+        self.dash = [dash.dash, dash.gap, dash.offset, start];
+        self.data[2] = dash.round as i32;
+        self
+    }
+    // The stops live in Graphics, first and count index into them.
+    pub fn gradient(mut self, gradient: &Gradient, first: i32, count: i32) -> Self {
+        let (mode, values) = gradient.encode();
+        self.paint = [mode, first, count, 0];
+        self.gradient = values;
+        self
+    }
+    pub fn corner_radius(mut self, r: f32) -> Self {
+        self.params[0] = r.max(0.0);
+        self
+    }
+    // Inner radius of a ring as a fraction of the outer radius.
+    pub fn inner(mut self, r: f32) -> Self {
+        self.params[2] = r.clamp(0.0,1.0);
+        self
+    }
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Shape>() as wgpu::BufferAddress,
@@ -198,12 +389,109 @@ impl Shape {
                     offset: (std::mem::size_of::<[f32; 9]>() + std::mem::size_of::<i32>()) as wgpu::BufferAddress,
                     shader_location: 5,
                     format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 11]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 15]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Sint32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 19]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 23]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Sint32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 27]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 31]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 35]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 39]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float32x4,
                 }
             ]
         }
     }
 }
 
+// A texture from Graphics::load, cheap to copy and compare. It stays valid until the texture is
+// unloaded, drawing with a stale handle draws the error image.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub struct TextureHandle {
+    pub(crate) index: u32,
+    pub(crate) generation: u32,
+}
+
+// What the drawing methods take as an image, a path or a handle.
+pub trait ImageSource {
+    fn resolve(&self, g: &mut Graphics) -> TextureHandle;
+}
+
+impl<T: AsRef<str> + ?Sized> ImageSource for &T {
+    fn resolve(&self, g: &mut Graphics) -> TextureHandle {
+        g.path_texture(self.as_ref())
+    }
+}
+
+impl ImageSource for TextureHandle {
+    fn resolve(&self, g: &mut Graphics) -> TextureHandle {
+        g.handle_texture(*self)
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+// Widths of the borders of a nine slice image, in texture pixels.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self { left, top, right, bottom }
+    }
+    pub fn uniform(inset: f32) -> Self {
+        Self::new(inset,inset,inset,inset)
+    }
+}
+
 //TODO builder, for purposes of having state in the building process.
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -214,21 +502,64 @@ pub struct Graphics {
     pub outline_color: Color,
     pub outline: bool,
     pub outline_thickness: f32,
+    pub outline_dash: Option<Dash>,
+    // Cast behind filled shapes and images while set.
+    pub shadow: Option<Shadow>,
+    // Replaces fill_color for filled shapes while set.
+    pub fill_gradient: Option<Gradient>,
     pub shapes: Vec<Shape>,
-    pub position: [f32; 4],
-    // x, y, angle, layer(someday)
-    positions: Vec<[f32; 4]>,
-    pub(crate) images: HashMap<usize,String>,
-    pub(crate) textures: HashMap<String, crate::TextureInfo>,
+    pub shape_kinds: ShapeRegistry,
+    pub transform: Transform,
+    // rotate, scale and skew happen around this point
+    pub origin: [f32; 2],
+    transforms: Vec<(Transform, [f32; 2])>,
+    pub(crate) images: HashMap<usize,TextureHandle>,
+    pub(crate) textures: crate::texture::TextureStore,
     pub(crate) texture_bind_group_layout: wgpu::BindGroupLayout,
+    // Whether the adapter can filter Rgba32Float, TextureFormat::Float32 is Float16 without it.
+    float32_filterable: bool,
+    // Used for images without options of their own, including atlas pages.
+    pub default_texture_options: TextureOptions,
+    texture_options: HashMap<String, TextureOptions>,
     image_errors: Vec<String>,
+    pub(crate) gradient_stops: Vec<GradientStop>,
+    pub(crate) polygon_points: Vec<[f32; 2]>,
+    // The last gradient uploaded and its first stop, consecutive shapes share stops.
+    last_gradient: Option<(Gradient, i32)>,
+    // Index of the first shape drawn with each tag, see tag.
+    tags: Vec<(usize, Option<u64>)>,
+    pub(crate) mesh_vertices: Vec<Vertex>,
+    pub(crate) mesh_indices: Vec<u32>,
+    pub(crate) meshes: Vec<MeshDraw>,
+    // Packed images, their atlas page and where they are on it.
+    pub(crate) atlas: HashMap<TextureHandle, (TextureHandle, Rect)>,
+    // Pixels of the images that can go on an atlas page, kept to pack and unpack them.
+    packable: HashMap<TextureHandle, crate::texture::TextureData>,
+    // Where images and data files are read from.
+    assets: crate::assets::Assets,
+    // Set while hot reloading, see hot_reload.
+    watcher: Option<crate::watch::Watcher>,
+    // Files that aren't images, watched for Event::Reloaded only.
+    watched: Vec<String>,
+    // Textures made by create_texture, with the pixels the game changes.
+    dynamic: HashMap<TextureHandle,crate::pixels::PixelBuffer>,
+    // Set while loading in the background, see background_loading.
+    loader: Option<crate::loader::Loader>,
+    // Drawn in place of images still loading in the background, the error image when None.
+    pub placeholder: Option<String>,
+    // Time spent each frame uploading images decoded in the background, at least one goes up.
+    pub upload_budget: std::time::Duration,
 }
 
 
 #[allow(dead_code)]
 impl Graphics {
     pub(crate) const ERROR_IMG: &'static str = "error.png";
-    pub fn new(device: Rc<wgpu::Device>,queue: Rc<wgpu::Queue>) -> Self {
+    pub(crate) const WHITE_IMG: &'static str = "white.png";
+    const ATLAS_PAGE: &'static str = "atlas page ";
+    const ATLAS_PAGE_SIZE: u32 = 2048;
+    const ATLAS_PADDING: u32 = 2;
+    pub fn new(device: Rc<wgpu::Device>,queue: Rc<wgpu::Queue>,float32_filterable: bool) -> Self {
         let texture_bind_group_layout = device.create_bind_group_layout( &wgpu::BindGroupLayoutDescriptor {
             label: Some("texture_bind_group_layout"),
             entries: &[
@@ -236,7 +567,7 @@ impl Graphics {
                     binding: 0,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false},
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
                         view_dimension: wgpu::TextureViewDimension::D2,
                         multisampled: false
                     },
@@ -245,7 +576,7 @@ impl Graphics {
                 wgpu::BindGroupLayoutEntry {
                     binding: 1,
                     visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None
                 }
             ]
@@ -259,53 +590,248 @@ impl Graphics {
             outline_color: Color::BLACK,
             outline: false,
             outline_thickness: 1.0,
+            outline_dash: None,
+            shadow: None,
+            fill_gradient: None,
             shapes: vec![],
-            position: [0.0,0.0,0.0,0.0],
-            positions: vec![],
+            shape_kinds: ShapeRegistry::new(),
+            transform: Transform::IDENTITY,
+            origin: [0.0,0.0],
+            transforms: vec![],
             images: HashMap::new(),
-            textures: HashMap::new(),
+            textures: crate::texture::TextureStore::default(),
             texture_bind_group_layout,
+            float32_filterable,
+            default_texture_options: TextureOptions::default(),
+            texture_options: HashMap::new(),
             image_errors: vec![],
+            gradient_stops: vec![],
+            polygon_points: vec![],
+            last_gradient: None,
+            tags: vec![],
+            mesh_vertices: vec![],
+            mesh_indices: vec![],
+            meshes: vec![],
+            atlas: HashMap::new(),
+            packable: HashMap::new(),
+            assets: crate::assets::Assets::new(),
+            watcher: None,
+            watched: vec![],
+            dynamic: HashMap::new(),
+            loader: None,
+            placeholder: None,
+            upload_budget: std::time::Duration::from_millis(4),
         };
         s.clear_texture_cache();
         s
     }
     fn info<P>(&mut self,image: P) -> Option<&crate::TextureInfo> where P: AsRef<Path> {
         let path = image.as_ref().clone().to_str().unwrap();
-        return self.textures.get(path)
+        return self.textures.handle(path).and_then(|handle| self.textures.get(handle))
     }
-    pub fn load_dyn(&mut self, img: &DynamicImage, path: &str) {
-        let texture = crate::Texture::from_image(
+    // Options an image is loaded with. The error and white images stay crisp.
+    fn options_for(&self, path: &str) -> TextureOptions {
+        let mut options = match path {
+            Graphics::ERROR_IMG | Graphics::WHITE_IMG => TextureOptions::default(),
+            _ => self.texture_options.get(path).copied().unwrap_or(self.default_texture_options),
+        };
+        if options.format == TextureFormat::Float32 && !self.float32_filterable {
+            options.format = TextureFormat::Float16;
+        }
+        options
+    }
+    // Sets how an image is sampled, reloading it when it's already loaded. A packed image leaves
+    // its atlas page. Pages take the options of the images on them, setting options on one unpacks it.
+    pub fn set_texture_options(&mut self, img: &str, options: TextureOptions) -> &Self {
+        if img.starts_with(Graphics::ATLAS_PAGE) {
+            if let Some(page) = self.textures.handle(img) {
+                self.unpack(page);
+                self.textures.remove(page);
+            }
+            return self;
+        }
+        self.texture_options.insert(img.to_string(),options);
+        if let Some(handle) = self.textures.handle(img).filter(|handle| self.dynamic.contains_key(handle)) {
+            let pixels = self.dynamic.remove(&handle).unwrap();
+            self.upload_pixel_buffer(img,pixels);
+            return self;
+        }
+        // a packed image leaves its page to get a texture of its own
+        let packed = self.textures.handle(img).and_then(|handle| self.atlas.remove(&handle)).is_some();
+        if self.textures.forget(img) || packed {
+            self.cache(img);
+        }
+        self
+    }
+    // Makes a texture from an image in memory, named path. A texture already named path is replaced
+    // and keeps its handle.
+    pub fn load_dyn(&mut self, img: &DynamicImage, path: &str) -> Option<TextureHandle> {
+        let data = crate::texture::TextureData::new(img,&self.options_for(path));
+        self.load_data(data,path)
+    }
+    fn load_data(&mut self, data: crate::texture::TextureData, path: &str) -> Option<TextureHandle> {
+        // a page replaced by a file of the same name drops its images first
+        if let Some(page) = self.textures.handle(path) {
+            self.unpack(page);
+        }
+        let texture = crate::Texture::from_data(
             &self.device,
             &self.queue,
-            img,
+            &data,
             Some(path),
             &self.texture_bind_group_layout,
         );
         if texture.is_err() {
             println!("Error while Loading {} ->  {}",path,texture.err().unwrap());
-            return;
+            return None;
         } else {
+            let texture = texture.unwrap();
             let texture_info = crate::TextureInfo {
-                bind_group: texture.unwrap().bind_group,
+                texture: texture.texture,
+                bind_group: texture.bind_group,
                 path: path.to_string(),
                 alias: Some(path.to_string()),
-                width: img.width(),
-                height: img.height(),
+                width: data.width,
+                height: data.height,
             };
-            self.textures.insert(path.to_string(),texture_info);
+            let handle = self.textures.insert(path,texture_info);
+            self.dynamic.remove(&handle);
+            let packable = data.options.format == crate::paint::TextureFormat::Srgb8
+                && data.options.wrap == Wrap::Clamp
+                && !data.options.mipmaps
+                && path != Graphics::ERROR_IMG && path != Graphics::WHITE_IMG && !path.starts_with(Graphics::ATLAS_PAGE);
+            if packable {
+                self.packable.insert(handle,data);
+            } else {
+                self.packable.remove(&handle);
+            }
+            Some(handle)
         }
     }
-    pub fn load<P>(&mut self, image: P) -> bool where P: AsRef<Path> {
-        let path = image.as_ref().clone().to_str().unwrap();
-        if self.textures.contains_key(path) {
+    // A texture of width by height the game draws into through pixels_mut, drawn like any image by
+    // handle or by name. Held like load until released, always 8 bit sRGB without mipmaps.
+    pub fn create_texture(&mut self, name: &str, width: u32, height: u32) -> TextureHandle {
+        let handle = self.upload_pixel_buffer(name,crate::pixels::PixelBuffer::new(width,height));
+        self.textures.acquire(handle);
+        handle
+    }
+    pub fn pixels(&self, handle: TextureHandle) -> Option<&crate::pixels::PixelBuffer> {
+        self.dynamic.get(&handle)
+    }
+    // None for textures not made by create_texture.
+    pub fn pixels_mut(&mut self, handle: TextureHandle) -> Option<&mut crate::pixels::PixelBuffer> {
+        self.dynamic.get_mut(&handle)
+    }
+    // Makes the texture for a pixel buffer from all of its pixels.
+    fn upload_pixel_buffer(&mut self, name: &str, mut pixels: crate::pixels::PixelBuffer) -> TextureHandle {
+        let options = TextureOptions { format: crate::paint::TextureFormat::Srgb8, mipmaps: false, ..self.options_for(name) };
+        let image = DynamicImage::ImageRgba8(
+            image::RgbaImage::from_raw(pixels.width(),pixels.height(),pixels.pixels().to_vec()).unwrap()
+        );
+        let texture = crate::Texture::from_image(
+            &self.device,
+            &self.queue,
+            &image,
+            Some(name),
+            &self.texture_bind_group_layout,
+            &options,
+        ).expect("Couldn't create a texture.");
+        pixels.take_dirty();
+        let handle = self.textures.insert(name,crate::TextureInfo {
+            texture: texture.texture,
+            bind_group: texture.bind_group,
+            path: name.to_string(),
+            alias: Some(name.to_string()),
+            width: pixels.width(),
+            height: pixels.height(),
+        });
+        self.dynamic.insert(handle,pixels);
+        handle
+    }
+    // Writes the changed part of every pixel buffer to its texture.
+    pub(crate) fn upload_pixels(&mut self) {
+        for (handle,pixels) in self.dynamic.iter_mut() {
+            let texture = match self.textures.get(*handle) {
+                Some(info) => &info.texture,
+                None => continue,
+            };
+            let [x0,y0,x1,y1] = match pixels.take_dirty() {
+                Some(dirty) => dirty,
+                None => continue,
+            };
+            let width = pixels.width();
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: x0, y: y0, z: 0 },
+                },
+                pixels.pixels(),
+                wgpu::ImageDataLayout {
+                    offset: (y0 as u64 * width as u64 + x0 as u64) * 4,
+                    bytes_per_row: std::num::NonZeroU32::new(width * 4),
+                    rows_per_image: std::num::NonZeroU32::new(y1 - y0),
+                },
+                wgpu::Extent3d {
+                    width: x1 - x0,
+                    height: y1 - y0,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+    }
+    // Loads an image and holds a reference to it until release, None when it can't be loaded.
+    // Drawing by path loads images too, those stay until unloaded.
+    pub fn load<P>(&mut self, image: P) -> Option<TextureHandle> where P: AsRef<Path> {
+        let path = image.as_ref().to_str()?;
+        if !self.cache(path) {
+            return None;
+        }
+        let handle = self.textures.handle(path)?;
+        self.textures.acquire(handle);
+        Some(handle)
+    }
+    // Drops a reference from load, the texture is unloaded with the last one.
+    pub fn release(&mut self, handle: TextureHandle) -> &Self {
+        if self.textures.release(handle) == Some(0) {
+            self.unload(handle);
+        }
+        self
+    }
+    // Frees a texture now, whatever its references, and its handle goes stale.
+    pub fn unload(&mut self, handle: TextureHandle) -> &Self {
+        if matches!(self.textures.name(handle), Some(Graphics::ERROR_IMG | Graphics::WHITE_IMG)) {
+            return self;
+        }
+        self.unpack(handle);
+        if let Some(name) = self.textures.remove(handle) {
+            self.dynamic.remove(&handle);
+            self.packable.remove(&handle);
+            if let Some(loader) = &mut self.loader {
+                loader.cancel(&name);
+            }
+            self.atlas.remove(&handle);
+            if let Some(watcher) = &self.watcher {
+                watcher.unwatch(&name);
+            }
+        }
+        self
+    }
+    // Loads an image from its file unless it's cached, false when it can't be.
+    pub(crate) fn cache(&mut self, path: &str) -> bool {
+        if self.is_resident(path) {
             return true;
         }
         if self.image_errors.contains(&String::from(path)) {
             return false;
         }
 
-        let img = image::open(&image);
+        // broken and missing images are watched too, so fixing them shows up
+        if let Some(watcher) = &self.watcher {
+            watcher.watch(path,Some(self.options_for(path)));
+        }
+        let img = self.assets.open_image(path);
         if img.is_ok() {
             println!("Loading Image: {}",path);
             let dyn_img = img.unwrap();
@@ -313,20 +839,31 @@ impl Graphics {
             return true
         }
         if !self.image_errors.contains(&String::from(path)) {
-            println!("Unable to load image: {} from location: {:?}",path,std::env::current_dir());
+            println!("Unable to load image: {} -> {}",path,img.err().unwrap());
             self.image_errors.push(String::from(path));
         }
         return false
     }
+    // Starts a new frame, this also resets the transform.
     pub fn clear(&mut self) -> &Self {
         self.shapes.clear();
         self.images.clear();
-        self.positions.clear();
+        self.reset_transform();
+        self.gradient_stops.clear();
+        self.polygon_points.clear();
+        self.last_gradient = None;
+        self.tags.clear();
+        self.mesh_vertices.clear();
+        self.mesh_indices.clear();
+        self.meshes.clear();
         self
     }
+    // Forgets every texture, they load again from their files when next drawn. Handles stay valid.
     pub fn clear_texture_cache(&mut self) -> &Self {
         self.image_errors.clear();
-        self.textures.clear();
+        self.remove_atlas_pages();
+        self.textures.forget_all();
+        self.packable.clear();
 
         let mut error = image::DynamicImage::new_rgba8(16,16);
         for x in 0..error.width() as i32 {
@@ -341,12 +878,206 @@ impl Graphics {
             }
         }
         self.load_dyn(&error, Graphics::ERROR_IMG);
+        let white = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1,1,Rgba::from([255,255,255,255])));
+        self.load_dyn(&white, Graphics::WHITE_IMG);
+        // created textures aren't files, they're made again from their pixels
+        let dynamic: Vec<(TextureHandle,crate::pixels::PixelBuffer)> = self.dynamic.drain()
+            .collect();
+        for (handle,pixels) in dynamic {
+            if let Some(name) = self.textures.name(handle).map(String::from) {
+                self.upload_pixel_buffer(&name,pixels);
+            }
+        }
         self
     }
+    // Where every loader reads files from, add search paths, packs and embedded files here. Images
+    // that failed to load are retried after clear_texture_cache.
+    pub fn assets(&self) -> &crate::assets::Assets {
+        &self.assets
+    }
+    // Watches the files of loaded images and reloads the ones that change in place, decoding them
+    // and making their mip levels off the render thread. The game gets an Event::Reloaded with the
+    // path of every changed file.
+    pub fn hot_reload(&mut self, enabled: bool) -> &Self {
+        if !enabled {
+            self.watcher = None;
+            return self;
+        }
+        if self.watcher.is_none() {
+            let watcher = crate::watch::Watcher::new(self.assets.clone());
+            let packed = self.atlas.keys().filter_map(|handle| self.textures.name(*handle));
+            for path in self.textures.names().map(String::as_str).chain(packed).chain(self.image_errors.iter().map(String::as_str)) {
+                if self.assets.locate(path).is_some() {
+                    watcher.watch(path,Some(self.options_for(path)));
+                }
+            }
+            for path in &self.watched {
+                watcher.watch(path,None);
+            }
+            self.watcher = Some(watcher);
+        }
+        self
+    }
+    // Reports changes to any other file, like sprite data, while hot reloading.
+    pub fn watch(&mut self, path: &str) -> &Self {
+        if !self.watched.iter().any(|p| p == path) {
+            self.watched.push(path.to_string());
+        }
+        if let Some(watcher) = &self.watcher {
+            watcher.watch(path,None);
+        }
+        self
+    }
+    pub fn unwatch(&mut self, path: &str) -> &Self {
+        self.watched.retain(|p| p != path);
+        if let Some(watcher) = &self.watcher {
+            watcher.unwatch(path);
+        }
+        self
+    }
+    // Uploads the images that changed since the last frame, returns the paths of all changed files.
+    // A reloaded image leaves its atlas page, pack_atlas again to put it back.
+    pub(crate) fn reload_changed(&mut self) -> Vec<String> {
+        let changes = match &self.watcher {
+            Some(watcher) => watcher.changes(),
+            None => return vec![],
+        };
+        let mut paths = vec![];
+        for change in changes {
+            if let Some(data) = change.image {
+                println!("Reloading Image: {}",change.path);
+                self.image_errors.retain(|p| *p != change.path);
+                if let Some(handle) = self.textures.handle(&change.path) {
+                    self.atlas.remove(&handle);
+                }
+                // options changed since it was watched, it loads again with the new ones
+                if data.options != self.options_for(&change.path) {
+                    self.textures.forget(&change.path);
+                    self.cache(&change.path);
+                } else {
+                    self.load_data(data,&change.path);
+                }
+            }
+            paths.push(change.path);
+        }
+        paths
+    }
+    // Decodes images on worker threads the first time they are drawn instead of stalling the frame,
+    // see placeholder and upload_budget. load still loads right away.
+    pub fn background_loading(&mut self, enabled: bool) -> &Self {
+        if !enabled {
+            self.loader = None;
+        } else if self.loader.is_none() {
+            self.loader = Some(crate::loader::Loader::new(self.assets.clone()));
+        }
+        self
+    }
+    // Starts loading an image in the background, or right away when not loading in the background.
+    pub fn preload(&mut self, img: &str) -> &Self {
+        let resident = self.is_resident(img);
+        let options = self.options_for(img);
+        match &mut self.loader {
+            Some(loader) if !resident => loader.request(img,options),
+            _ => {self.cache(img);}
+        }
+        self
+    }
+    pub fn is_loaded(&self, img: &str) -> bool {
+        self.is_resident(img)
+    }
+    // Loaded with a texture of its own, or on an atlas page.
+    fn is_resident(&self, img: &str) -> bool {
+        self.textures.is_loaded(img) || self.textures.handle(img).and_then(|handle| self.packed(handle)).is_some()
+    }
+    // An image's atlas page and where it is on it, while the page is loaded.
+    fn packed(&self, handle: TextureHandle) -> Option<(TextureHandle, Rect)> {
+        self.atlas.get(&handle).copied().filter(|(page,_)| self.textures.get(*page).is_some())
+    }
+    // Images still decoding in the background, for loading screens.
+    pub fn loading(&self) -> usize {
+        self.loader.as_ref().map_or(0, |loader| loader.len())
+    }
+    // Uploads images decoded in the background until the frame's budget is spent.
+    pub(crate) fn upload_loaded(&mut self) {
+        let start = std::time::Instant::now();
+        while let Some((path,data)) = self.loader.as_mut().and_then(|loader| loader.next()) {
+            if let Some(watcher) = &self.watcher {
+                watcher.watch(&path,Some(self.options_for(&path)));
+            }
+            match data {
+                // options changed while it was on its way
+                Ok(data) if data.options != self.options_for(&path) => {
+                    let options = self.options_for(&path);
+                    self.loader.as_mut().unwrap().request(&path,options);
+                }
+                Ok(data) => {
+                    println!("Loading Image: {}",path);
+                    self.load_data(data,&path);
+                }
+                Err(e) => {
+                    println!("Unable to load image: {} -> {}",path,e);
+                    self.image_errors.push(path);
+                }
+            }
+            if start.elapsed() >= self.upload_budget {
+                break;
+            }
+        }
+    }
+    // Adds a shape kind drawn with the given wgsl, see ShapeFunction. The shader is compiled right
+    // away, wgsl that doesn't compile isn't registered and a kind registered again keeps its old function.
+    pub fn register_shape(&mut self, name: &str, function: ShapeFunction) -> Result<ShapeKind,InvalidShape> {
+        let previous = self.shape_kinds.get(name)
+            .and_then(|kind| self.shape_kinds.definition(kind))
+            .map(|definition| definition.function.clone());
+        let changed = self.shape_kinds.changed;
+        let kind = self.shape_kinds.register(name,function);
+        let source = self.shape_kinds.shader_source(crate::renderer::SHAPE_SHADER);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(name),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        match pollster::block_on(self.device.pop_error_scope()) {
+            None => Ok(kind),
+            Some(e) => {
+                match previous {
+                    Some(function) => {self.shape_kinds.register(name,function);},
+                    None => self.shape_kinds.remove_last(),
+                }
+                self.shape_kinds.changed = changed;
+                Err(InvalidShape(e.to_string()))
+            }
+        }
+    }
     pub fn color(&mut self, color: Color) -> &Self {
         self.fill_color = color;
+        self.fill_gradient = None;
         self
     }
+    // Fills use the gradient until the next call to color.
+    pub fn gradient(&mut self, gradient: Gradient) -> &Self {
+        self.fill_gradient = Some(gradient);
+        self
+    }
+    // Applies the current fill color or gradient to a filled shape.
+    fn fill_paint(&mut self, shape: Shape) -> Shape {
+        let gradient = match &self.fill_gradient {
+            Some(g) if !g.stops.is_empty() => g.clone(),
+            _ => return shape.color(self.fill_color),
+        };
+        let count = gradient.stops.len() as i32;
+        let first = match &self.last_gradient {
+            Some((last, first)) if *last == gradient => *first,
+            _ => {
+                let first = self.gradient_stops.len() as i32;
+                self.gradient_stops.extend(gradient.stops.iter().map(|(o,c)| GradientStop::new(*o,*c)));
+                self.last_gradient = Some((gradient.clone(),first));
+                first
+            }
+        };
+        shape.color(Color::WHITE).gradient(&gradient,first,count)
+    }
     pub fn outline_color(&mut self, color: Color) -> &Self {
         self.outline_color = color;
         self
@@ -355,129 +1086,706 @@ impl Graphics {
         self.outline_thickness = thickness;
         self
     }
+    // Dashes rect, round rect, oval, arc and line outlines, None draws them solid.
+    // Pie, ring and polygon outlines stay solid, draw a closed polyline for a dashed polygon.
+    pub fn outline_dash(&mut self, dash: Option<Dash>) -> &Self {
+        self.outline_dash = dash;
+        self
+    }
+    fn dashed(&self, shape: Shape, start: f32) -> Shape {
+        match &self.outline_dash {
+            Some(dash) => shape.dash(dash, start),
+            None => shape,
+        }
+    }
+    // Shadows behind filled shapes and images drawn after this, None for no shadow.
+    pub fn shadow(&mut self, shadow: Option<Shadow>) -> &Self {
+        self.shadow = shadow;
+        self
+    }
+    fn cast_shadow(&mut self, shape: &Shape) {
+        if let Some(shadow) = self.shadow.as_ref().and_then(|shadow| Shape::shadow(shape,shadow)) {
+            self.shapes.push(shadow);
+        }
+    }
     pub fn outline(&mut self, value: bool) -> &Self {
         self.outline = value;
         self
     }
-    pub fn set_rotation(&mut self, angle: f32) -> &Self {
-        self.position[2] = angle;
+    // Shapes drawn after this carry the tag, until the next call or clear.
+    pub fn tag(&mut self, tag: Option<u64>) -> &Self {
+        self.tags.push((self.shapes.len(),tag));
         self
     }
-    pub fn rotate(&mut self, amount: f32) -> &Self {
-        self.position[2] += amount;
+    pub fn tag_of(&self, index: usize) -> Option<u64> {
+        self.tags.iter().rev()
+            .find(|(first,_)| *first <= index)
+            .and_then(|(_,tag)| *tag)
+    }
+    // Indices of the shapes under a point, topmost first. Shapes stay around until clear,
+    // so this can be asked while handling input, about the last frame drawn.
+    pub fn hits(&self, x: f32, y: f32) -> Vec<usize> {
+        (0..self.shapes.len()).rev()
+            .filter(|i| self.shapes[*i].contains([x,y],&self.polygon_points))
+            .collect()
+    }
+    // Index of the topmost shape under a point.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<usize> {
+        (0..self.shapes.len()).rev()
+            .find(|i| self.shapes[*i].contains([x,y],&self.polygon_points))
+    }
+    // Tag of the topmost shape under a point, None when it has no tag.
+    pub fn hit_tag(&self, x: f32, y: f32) -> Option<u64> {
+        self.hit_test(x,y).and_then(|i| self.tag_of(i))
+    }
+    pub fn rotate(&mut self, angle: f32) -> &Self {
+        self.apply(Transform::rotation(angle))
+    }
+    pub fn scale(&mut self, sx: f32, sy: f32) -> &Self {
+        self.apply(Transform::scaling(sx,sy))
+    }
+    pub fn skew(&mut self, kx: f32, ky: f32) -> &Self {
+        self.apply(Transform::skewing(kx,ky))
+    }
+    pub fn translate(&mut self, x: f32,  y: f32) -> &Self {
+        self.transform = self.transform.multiply(&Transform::translation(x,y));
         self
     }
-    pub fn set_translation(&mut self, x: f32, y: f32) -> &Self {
-        self.position[0] = x;
-        self.position[1] = y;
+    // Point in the current coordinates that rotate, scale and skew pivot around.
+    pub fn set_origin(&mut self, x: f32, y: f32) -> &Self {
+        self.origin = [x,y];
         self
     }
-    pub fn translate(&mut self, x: f32,  y: f32) -> &Self {
-        self.position[0] += x;
-        self.position[1] += y;
+    // Applies t around the origin, after the current transform.
+    pub fn apply(&mut self, t: Transform) -> &Self {
+        self.transform = self.transform.multiply(&Transform::around(&t,self.origin));
         self
     }
-    pub fn push_position(&mut self) -> &Self {
-        self.positions.push(self.position);
+    pub fn set_transform(&mut self, t: Transform) -> &Self {
+        self.transform = t;
         self
     }
-    pub fn pop_position(&mut self) -> &Self {
-        if !self.positions.is_empty() {
-            self.position = self.positions.pop().unwrap();
+    pub fn reset_transform(&mut self) -> &Self {
+        self.transform = Transform::IDENTITY;
+        self.origin = [0.0,0.0];
+        self.transforms.clear();
+        self
+    }
+    pub fn push_transform(&mut self) -> &Self {
+        self.transforms.push((self.transform,self.origin));
+        self
+    }
+    pub fn pop_transform(&mut self) -> &Self {
+        if let Some((transform,origin)) = self.transforms.pop() {
+            self.transform = transform;
+            self.origin = origin;
         }
         self
     }
-    fn apply_position(&self, x: f32, y: f32, a: f32) -> (f32,f32,f32) {
-        return (self.position[0] + x,self.position[1] + y, self.position[2] + a);
+    // Top left of a shape whose transformed center stays put, and the 2x2 part of the transform.
+    pub(crate) fn apply_transform(&self, x: f32, y: f32, width: f32, height: f32) -> (f32,f32,[f32; 4]) {
+        let center = self.transform.apply([x + width / 2.0, y + height / 2.0]);
+        (center[0] - width / 2.0, center[1] - height / 2.0, self.transform.linear())
     }
-
-    fn shape(&mut self, k: ShapeKind, x: f32, y: f32, width: f32, height: f32) -> &Self {
-        let (x,y,a) = self.apply_position(x,y,0.0);
-        self.shapes.push(
+    // Draws a shape of any kind, like the ones registered in shape_kinds.
+    pub fn shape(&mut self, kind: ShapeKind, x: f32, y: f32, width: f32, height: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let shape = self.fill_paint(
             Shape::rect(x,y,width,height)
-                .color(self.fill_color)
-                .angle(a)
-                .kind(k)
+                .kind(kind)
+                .transform(m)
+                .thickness(self.outline_thickness)
         );
+        self.shapes.push(shape);
         self
     }
     pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
-        let (x,y,a) = self.apply_position(x,y,0.0);
-        self.shapes.push(
-          Shape::rect(x,y,width,height)
-              .color(self.fill_color)
-              .angle(a)
-              .thickness(self.outline_thickness)
-        );
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let shape = Shape::rect(x,y,width,height)
+            .color(self.fill_color)
+            .transform(m)
+            .thickness(self.outline_thickness);
+        self.shapes.push(self.dashed(shape,0.0));
         self
     }
     pub fn oval(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
-        let (x,y,a) = self.apply_position(x,y,0.0);
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let shape = Shape::oval(x,y,width,height)
+            .color(self.fill_color)
+            .transform(m)
+            .thickness(self.outline_thickness);
+        self.shapes.push(self.dashed(shape,0.0));
+        self
+    }
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+
+        let shape = self.fill_paint(Shape::fill_rect(x, y, width, height).transform(m));
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+
+        if self.outline {
+            let shape = Shape::rect(x,y,width,height)
+                .color(self.outline_color)
+                .thickness(self.outline_thickness)
+                .transform(m);
+            self.shapes.push(self.dashed(shape,0.0));
+        }
+        self
+    }
+    pub fn fill_oval(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+
+        let shape = self.fill_paint(Shape::fill_oval(x, y, width, height).transform(m));
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+
+        if self.outline {
+            let shape = Shape::oval(x,y,width,height)
+                .color(self.outline_color)
+                .thickness(self.outline_thickness)
+                .transform(m);
+            self.shapes.push(self.dashed(shape,0.0));
+        }
+        self
+    }
+    pub fn round_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let shape = Shape::round_rect(x,y,width,height,radius)
+            .color(self.fill_color)
+            .transform(m)
+            .thickness(self.outline_thickness);
+        self.shapes.push(self.dashed(shape,0.0));
+        self
+    }
+    pub fn fill_round_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+
+        let shape = self.fill_paint(Shape::fill_round_rect(x, y, width, height, radius).transform(m));
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+
+        if self.outline {
+            let shape = Shape::round_rect(x,y,width,height,radius)
+                .color(self.outline_color)
+                .thickness(self.outline_thickness)
+                .transform(m);
+            self.shapes.push(self.dashed(shape,0.0));
+        }
+        self
+    }
+    pub fn arc(&mut self, x: f32, y: f32, width: f32, height: f32, start: f32, end: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let shape = Shape::arc(x,y,width,height,start,end)
+            .color(self.fill_color)
+            .transform(m)
+            .thickness(self.outline_thickness);
+        self.shapes.push(self.dashed(shape,0.0));
+        self
+    }
+    pub fn pie(&mut self, x: f32, y: f32, width: f32, height: f32, start: f32, end: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
         self.shapes.push(
-            Shape::oval(x,y,width,height)
+            Shape::pie(x,y,width,height,start,end)
                 .color(self.fill_color)
-                .angle(a)
+                .transform(m)
                 .thickness(self.outline_thickness)
         );
         self
     }
-    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
-        let (x,y,a) = self.apply_position(x,y,0.0);
+    pub fn fill_pie(&mut self, x: f32, y: f32, width: f32, height: f32, start: f32, end: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
 
-        self.shapes.push(
-            Shape::fill_rect(x, y, width, height)
-            .color(self.fill_color)
-            .angle(a)
-        );
+        let shape = self.fill_paint(Shape::fill_pie(x, y, width, height, start, end).transform(m));
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
 
         if self.outline {
             self.shapes.push(
-                Shape::rect(x,y,width,height)
+                Shape::pie(x,y,width,height,start,end)
                     .color(self.outline_color)
                     .thickness(self.outline_thickness)
-                    .angle(a)
+                    .transform(m)
             );
         }
         self
     }
-    pub fn fill_oval(&mut self, x: f32, y: f32, width: f32, height: f32) -> &Self {
-        let (x,y,a) = self.apply_position(x,y,0.0);
-
+    // inner is the hole's radius as a fraction of the outer radius.
+    #[allow(clippy::too_many_arguments)]
+    pub fn ring(&mut self, x: f32, y: f32, width: f32, height: f32, inner: f32, start: f32, end: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
         self.shapes.push(
-            Shape::fill_oval(x, y, width, height)
+            Shape::ring(x,y,width,height,inner)
+                .angles(start,end)
                 .color(self.fill_color)
-                .angle(a)
+                .transform(m)
+                .thickness(self.outline_thickness)
         );
+        self
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_ring(&mut self, x: f32, y: f32, width: f32, height: f32, inner: f32, start: f32, end: f32) -> &Self {
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+
+        let shape = self.fill_paint(Shape::fill_ring(x, y, width, height, inner).angles(start,end).transform(m));
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
 
         if self.outline {
             self.shapes.push(
-                Shape::oval(x,y,width,height)
+                Shape::ring(x,y,width,height,inner)
+                    .angles(start,end)
                     .color(self.outline_color)
                     .thickness(self.outline_thickness)
-                    .angle(a)
+                    .transform(m)
             );
         }
         self
     }
-    pub fn image(&mut self,img: &str, x: f32, y: f32) -> &Self {
-        let mut image = img;
-        if !self.load(img) {
-            image = Graphics::ERROR_IMG;
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) -> &Self {
+        self.segment([x1,y1],[x2,y2],0.0);
+        self
+    }
+    // Returns the segment's length, for continuing a dash pattern.
+    fn segment(&mut self, from: [f32; 2], to: [f32; 2], start: f32) -> f32 {
+        let line = Shape::line(from[0],from[1],to[0],to[1],self.outline_thickness);
+        let (x,y,m) = self.apply_transform(line.x,line.y,line.width,line.height);
+        let shape = Shape { x, y, ..line }
+            .color(self.fill_color)
+            .transform(m);
+        self.shapes.push(self.dashed(shape,start));
+        ((to[0] - from[0]).powi(2) + (to[1] - from[1]).powi(2)).sqrt()
+    }
+    // One shape for the whole line, unless dashed, where each segment carries on the dash pattern.
+    pub fn polyline(&mut self, points: &[[f32; 2]], closed: bool) -> &Self {
+        if self.outline_dash.is_none() && points.len() > 2 {
+            let mut points = points.to_vec();
+            if closed && points[0] != points[points.len() - 1] {
+                points.push(points[0]);
+            }
+            if let Some((x,y,width,height,first,count)) = self.polygon_points(&points) {
+                let line = Shape::polyline(x,y,width,height,first,count,self.outline_thickness);
+                let (x,y,m) = self.apply_transform(line.x,line.y,line.width,line.height);
+                self.shapes.push(Shape { x, y, ..line }.color(self.fill_color).transform(m));
+            }
+            return self;
         }
+        let mut length = 0.0;
+        for pair in points.windows(2) {
+            length += self.segment(pair[0],pair[1],length);
+        }
+        if closed && points.len() > 2 {
+            let (first, last) = (points[0], points[points.len() - 1]);
+            if first != last {
+                self.segment(last,first,length);
+            }
+        }
+        self
+    }
+    // Bounds of the points, and the points moved relative to the center of the bounds.
+    fn polygon_points(&mut self, points: &[[f32; 2]]) -> Option<(f32, f32, f32, f32, i32, i32)> {
+        if points.len() < 3 {
+            return None;
+        }
+        let mut min = points[0];
+        let mut max = points[0];
+        for p in points {
+            min = [min[0].min(p[0]), min[1].min(p[1])];
+            max = [max[0].max(p[0]), max[1].max(p[1])];
+        }
+        let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let first = self.polygon_points.len() as i32;
+        self.polygon_points.extend(points.iter().map(|p| [p[0] - center[0], p[1] - center[1]]));
+        Some((min[0], min[1], max[0] - min[0], max[1] - min[1], first, points.len() as i32))
+    }
+    pub fn polygon(&mut self, points: &[[f32; 2]]) -> &Self {
+        if let Some((x,y,width,height,first,count)) = self.polygon_points(points) {
+            let (x,y,m) = self.apply_transform(x,y,width,height);
+            self.shapes.push(
+                Shape::polygon(x,y,width,height,first,count)
+                    .color(self.fill_color)
+                    .transform(m)
+                    .thickness(self.outline_thickness)
+            );
+        }
+        self
+    }
+    pub fn fill_polygon(&mut self, points: &[[f32; 2]]) -> &Self {
+        if let Some((x,y,width,height,first,count)) = self.polygon_points(points) {
+            let (x,y,m) = self.apply_transform(x,y,width,height);
 
-        let (x,y,a) = self.apply_position(x,y,0.0);
+            let shape = self.fill_paint(Shape::fill_polygon(x, y, width, height, first, count).transform(m));
+            self.cast_shadow(&shape);
+            self.shapes.push(shape);
 
-        let tex_info = self.textures.get(image).unwrap();
+            if self.outline {
+                self.shapes.push(
+                    Shape::polygon(x,y,width,height,first,count)
+                        .color(self.outline_color)
+                        .thickness(self.outline_thickness)
+                        .transform(m)
+                );
+            }
+        }
+        self
+    }
+    // Curve flattening tolerance in local units, so scaled up curves stay smooth.
+    fn tolerance(&self) -> f32 {
+        crate::path::Path::TOLERANCE / self.transform.determinant().abs().sqrt().max(0.0001)
+    }
+    pub fn quad_curve(&mut self, from: [f32; 2], ctrl: [f32; 2], to: [f32; 2]) -> &Self {
+        let mut points = vec![from];
+        crate::path::flatten_quad(from, ctrl, to, self.tolerance(), &mut points);
+        self.polyline(&points, false)
+    }
+    pub fn cubic_curve(&mut self, from: [f32; 2], ctrl1: [f32; 2], ctrl2: [f32; 2], to: [f32; 2]) -> &Self {
+        let mut points = vec![from];
+        crate::path::flatten_cubic(from, ctrl1, ctrl2, to, self.tolerance(), &mut points);
+        self.polyline(&points, false)
+    }
+    pub fn stroke_path(&mut self, path: &crate::path::Path) -> &Self {
+        for sub in path.flatten(self.tolerance()) {
+            self.polyline(&sub.points, sub.closed);
+        }
+        self
+    }
+    // The cached texture for an image, or the error texture when it can't be loaded.
+    // Images packed by pack_atlas come from their atlas page. While loading in the background,
+    // images not loaded yet are queued and the placeholder is drawn in their place, at its size.
+    fn texture(&mut self, img: impl ImageSource) -> TextureRegion {
+        let mut handle = img.resolve(self);
+        if let Some((page,rect)) = self.packed(handle) {
+            let info = self.textures.get(page).unwrap();
+            let (w,h) = (info.width as f32,info.height as f32);
+            return TextureRegion {
+                handle: page,
+                width: rect.width,
+                height: rect.height,
+                uv: [rect.x / w, rect.y / h, (rect.x + rect.width) / w, (rect.y + rect.height) / h],
+            };
+        }
+        if self.textures.get(handle).is_none() {
+            handle = self.error_texture();
+        }
+        let tex_info = self.textures.get(handle).unwrap();
+        TextureRegion {
+            handle,
+            width: tex_info.width as f32,
+            height: tex_info.height as f32,
+            uv: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+    // The texture to draw for a path, see texture.
+    pub(crate) fn path_texture(&mut self, img: &str) -> TextureHandle {
+        let waiting = self.loader.is_some()
+            && !self.is_resident(img)
+            && !self.image_errors.iter().any(|e| e == img);
+        let image = if waiting {
+            let options = self.options_for(img);
+            self.loader.as_mut().unwrap().request(img,options);
+            match self.placeholder.clone() {
+                Some(placeholder) if placeholder != img && self.cache(&placeholder) => placeholder,
+                _ => String::from(Graphics::ERROR_IMG),
+            }
+        } else if self.cache(img) {
+            String::from(img)
+        } else {
+            String::from(Graphics::ERROR_IMG)
+        };
+        self.textures.handle(&image).unwrap_or_else(|| self.error_texture())
+    }
+    // The texture to draw for a handle. Forgotten textures load again by name, stale handles draw
+    // the error texture.
+    pub(crate) fn handle_texture(&mut self, handle: TextureHandle) -> TextureHandle {
+        if self.textures.get(handle).is_some() || self.packed(handle).is_some() {
+            return handle;
+        }
+        match self.textures.name(handle).map(String::from) {
+            Some(name) => self.path_texture(&name),
+            None => self.error_texture(),
+        }
+    }
+    pub(crate) fn error_texture(&self) -> TextureHandle {
+        self.textures.handle(Graphics::ERROR_IMG).expect("The error texture is always loaded.")
+    }
+    fn remove_atlas_pages(&mut self) {
+        let pages: Vec<TextureHandle> = self.textures.names()
+            .filter(|name| name.starts_with(Graphics::ATLAS_PAGE))
+            .filter_map(|name| self.textures.handle(name))
+            .collect();
+        for page in pages {
+            self.textures.remove(page);
+        }
+        self.atlas.clear();
+    }
+    // Gives the images on a page their own textures back, before the page goes away.
+    fn unpack(&mut self, page: TextureHandle) {
+        let images: Vec<TextureHandle> = self.atlas.iter()
+            .filter(|(_,(p,_))| *p == page)
+            .map(|(image,_)| *image)
+            .collect();
+        for image in images {
+            self.atlas.remove(&image);
+            if let (Some(name),Some(data)) = (self.textures.name(image).map(String::from),self.packable.remove(&image)) {
+                self.load_data(data,&name);
+            }
+        }
+    }
+    // Packs the loaded images into a few large textures, so drawing them doesn't switch textures.
+    // Drawing an image afterwards uses its place in the atlas, and its own texture is freed.
+    // Returns how many got packed. Only 8 bit images that clamp and have no mipmaps are packed,
+    // smaller levels and wrapping would bleed into their neighbours. Images share pages with the
+    // images of the same options, ones too big for a page and ones from create_texture keep their
+    // own texture. Packing reads the pixels kept on the cpu when the images loaded.
+    pub fn pack_atlas(&mut self) -> usize {
+        let mut images: Vec<(String, TextureHandle)> = self.packable.keys()
+            .filter(|handle| self.textures.get(**handle).is_some() || self.packed(**handle).is_some())
+            .filter_map(|handle| Some((self.textures.name(*handle)?.to_string(),*handle)))
+            .collect();
+        images.sort_by(|a,b| a.0.cmp(&b.0));
+        let mut groups: Vec<(TextureOptions, Vec<(String, image::RgbaImage)>)> = vec![];
+        for (name,handle) in images {
+            let data = &self.packable[&handle];
+            let pixels = image::RgbaImage::from_raw(data.width,data.height,data.levels[0].clone()).unwrap();
+            match groups.iter_mut().find(|(options,_)| *options == data.options) {
+                Some((_,group)) => group.push((name,pixels)),
+                None => groups.push((data.options,vec![(name,pixels)])),
+            }
+        }
+        self.remove_atlas_pages();
+        let mut count = 0;
+        for (options,images) in groups {
+            let (pages,regions) = crate::atlas::build(&images, Graphics::ATLAS_PAGE_SIZE, Graphics::ATLAS_PADDING);
+            let pages: Vec<Option<TextureHandle>> = pages.into_iter()
+                .map(|page| {
+                    let data = crate::texture::TextureData::new(&DynamicImage::ImageRgba8(page),&options);
+                    let name = format!("{}{}",Graphics::ATLAS_PAGE,count);
+                    count += 1;
+                    self.load_data(data,&name)
+                })
+                .collect();
+            for (name,_) in &images {
+                let image = self.textures.handle(name).unwrap();
+                match regions.get(name).and_then(|(page,rect)| Some(((*pages.get(*page)?)?,*rect))) {
+                    Some(region) => {
+                        self.atlas.insert(image,region);
+                        self.textures.forget(name);
+                    }
+                    // it was on a page before and didn't fit this time
+                    None if self.textures.get(image).is_none() => {
+                        let data = self.packable.remove(&image).unwrap();
+                        self.load_data(data,name);
+                    }
+                    None => {}
+                }
+            }
+        }
+        self.atlas.len()
+    }
+    pub fn image(&mut self,img: impl ImageSource, x: f32, y: f32) -> &Self {
+        let texture = self.texture(img);
+        let (width,height) = (texture.width,texture.height);
 
-        self.shapes.push(
-            Shape::rect(x,y,tex_info.width as f32,tex_info.height as f32)
-                .kind(TEX_RECT)
-                .color(self.fill_color)
-                .angle(a)
-        );
-        self.images.insert(self.shapes.len()-1,String::from(image));
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+
+        let [u0,v0,u1,v1] = texture.uv;
+        let shape = Shape::rect(x,y,width,height)
+            .kind(ShapeKind::TexRect)
+            .uv(u0,v0,u1,v1)
+            .color(self.fill_color)
+            .transform(m);
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+        self.images.insert(self.shapes.len()-1,texture.handle);
 
 
         self
     }
-}
\ No newline at end of file
+    // Triangles from vertices in pixels, three indices each, under the current transform.
+    // Vertex colors are used as they are, the image is stretched by the vertex uvs when given.
+    // Images packed by pack_atlas have no room to wrap, their uvs are clamped to 0..1 at each vertex.
+    pub fn mesh(&mut self, vertices: &[Vertex], indices: &[u32], img: Option<&dyn ImageSource>) -> Result<&Self,InvalidMesh> {
+        let count = indices.len() - indices.len() % 3;
+        if count == 0 {
+            return Ok(self);
+        }
+        if let Some(i) = indices[..count].iter().find(|i| **i as usize >= vertices.len()) {
+            return Err(InvalidMesh { index: *i, vertices: vertices.len() });
+        }
+        let (image,uv) = match img {
+            Some(img) => {
+                let handle = img.resolve(self);
+                let texture = self.texture(handle);
+                (texture.handle,texture.uv)
+            },
+            None => (self.path_texture(Graphics::WHITE_IMG),[0.0, 0.0, 1.0, 1.0]),
+        };
+        let transform = self.transform;
+        let packed = uv != [0.0, 0.0, 1.0, 1.0];
+        self.meshes.push(MeshDraw {
+            at: self.shapes.len(),
+            base_vertex: self.mesh_vertices.len() as i32,
+            first_index: self.mesh_indices.len() as u32,
+            count: count as u32,
+            image,
+        });
+        self.mesh_vertices.extend(vertices.iter().map(|vertex| {
+            let [x,y] = transform.apply([vertex.x,vertex.y]);
+            let (u,v) = if packed { (vertex.u.clamp(0.0,1.0),vertex.v.clamp(0.0,1.0)) } else { (vertex.u,vertex.v) };
+            let u = uv[0] + u * (uv[2] - uv[0]);
+            let v = uv[1] + v * (uv[3] - uv[1]);
+            Vertex { x, y, u, v, ..*vertex }
+        }));
+        self.mesh_indices.extend_from_slice(&indices[..count]);
+        Ok(self)
+    }
+    pub fn quad(&mut self, quad: &Quad, img: Option<&dyn ImageSource>) -> &Self {
+        // the indices of a quad are always in range
+        let _ = self.mesh(&[quad.tl,quad.tr,quad.br,quad.bl],&[0,1,2,0,2,3],img);
+        self
+    }
+    // Draws the part of an image inside src, in texture pixels, stretched over dst.
+    pub fn image_region(&mut self, img: impl ImageSource, src: Rect, dst: Rect) -> &Self {
+        let texture = self.texture(img);
+        let (x,y,m) = self.apply_transform(dst.x,dst.y,dst.width,dst.height);
+        let [u0,v0,u1,v1] = texture.sub(src);
+        let shape = Shape::rect(x,y,dst.width,dst.height)
+            .kind(ShapeKind::TexRect)
+            .uv(u0,v0,u1,v1)
+            .color(self.fill_color)
+            .transform(m);
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+        self.images.insert(self.shapes.len()-1,texture.handle);
+        self
+    }
+    // A sheet of grid frames covering the whole image, which is loaded for its size right away,
+    // even while loading in the background.
+    pub fn load_sprite_sheet(&mut self, img: &str, grid: Grid) -> SpriteSheet {
+        self.cache(img);
+        let texture = self.texture(img);
+        SpriteSheet::grid(img,texture.width,texture.height,grid)
+    }
+    // Reads TexturePacker or Aseprite json and loads the image it names, found next to the json file.
+    pub fn load_sprite_data<P>(&mut self, json: P) -> Option<SpriteData> where P: AsRef<Path> {
+        let json = json.as_ref();
+        let data = self.assets.read_to_string(&json.to_string_lossy())
+            .map_err(anyhow::Error::from)
+            .and_then(|text| SpriteData::parse(&text));
+        match data {
+            Ok(mut data) => {
+                // hot reloading reports changes to the json, loading it again is up to the game
+                self.watch(&json.to_string_lossy());
+                let image = json.parent().unwrap_or(Path::new("")).join(&data.sheet.image);
+                data.sheet.image = image.to_string_lossy().to_string();
+                self.cache(&data.sheet.image);
+                Some(data)
+            }
+            Err(e) => {
+                println!("Unable to load sprite data: {:?} -> {}",json,e);
+                None
+            }
+        }
+    }
+    // Draws a frame of a sprite sheet at its own size with its pivot at x,y, nothing when the sheet
+    // has no such frame. Trimmed frames land where they were before trimming, rotated ones upright.
+    pub fn sprite(&mut self, sheet: &SpriteSheet, frame: usize, x: f32, y: f32) -> &Self {
+        let frame = match sheet.get(frame) {
+            Some(frame) => *frame,
+            None => return self,
+        };
+        let src = frame.rect;
+        let x = x - frame.pivot[0] * frame.size[0] + frame.offset[0];
+        let y = y - frame.pivot[1] * frame.size[1] + frame.offset[1];
+        if !frame.rotated {
+            return self.image_region(&sheet.image,src,Rect::new(x,y,src.width,src.height));
+        }
+        // turned back a quarter around the center of where the upright sprite goes
+        let center = [x + src.height / 2.0, y + src.width / 2.0];
+        self.push_transform();
+        self.set_origin(center[0],center[1]);
+        self.rotate(-std::f32::consts::FRAC_PI_2);
+        self.image_region(&sheet.image,src,Rect::new(center[0] - src.width / 2.0,center[1] - src.height / 2.0,src.width,src.height));
+        self.pop_transform();
+        self
+    }
+    // Repeats an image over width by height, one draw however many tiles it takes.
+    pub fn tile_image(&mut self, img: impl ImageSource, x: f32, y: f32, width: f32, height: f32, tiling: Tiling) -> &Self {
+        let texture = self.texture(img);
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let [u0,v0,u1,v1] = texture.uv;
+        let shape = Shape::rect(x,y,width,height)
+            .kind(ShapeKind::TexRect)
+            .uv(u0,v0,u1,v1)
+            .tiling(tiling.encode(width,height,texture.width,texture.height),tiling.wrap)
+            .color(self.fill_color)
+            .transform(m);
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+        self.images.insert(self.shapes.len()-1,texture.handle);
+        self
+    }
+    // Stretches an image to width by height, keeping the corners as they are and stretching
+    // the edges along one axis only. Corners shrink when they don't fit.
+    pub fn nine_slice(&mut self, img: impl ImageSource, x: f32, y: f32, width: f32, height: f32, insets: Insets) -> &Self {
+        let texture = self.texture(img);
+        let (x,y,m) = self.apply_transform(x,y,width,height);
+        let [u0,v0,u1,v1] = texture.uv;
+        let shape = Shape::nine_slice(x,y,width,height,insets)
+            .uv(u0,v0,u1,v1)
+            .color(self.fill_color)
+            .transform(m);
+        self.cast_shadow(&shape);
+        self.shapes.push(shape);
+        self.images.insert(self.shapes.len()-1,texture.handle);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{PI, TAU};
+
+    #[test]
+    fn angles_are_stored_in_order() {
+        let arc = Shape::arc(0.0,0.0,10.0,10.0,PI,0.5);
+        assert_eq!(arc.kind, ShapeKind::Arc.id());
+        assert_eq!(&arc.params[..2], &[0.5,PI]);
+        let pie = Shape::pie(0.0,0.0,10.0,10.0,0.5,PI);
+        assert_eq!(&pie.params[..2], &[0.5,PI]);
+    }
+
+    #[test]
+    fn shapes_default_to_a_full_turn() {
+        let ring = Shape::fill_ring(0.0,0.0,10.0,10.0,0.5);
+        assert_eq!(&ring.params[..2], &[0.0,TAU]);
+        assert_eq!(ring.params[2], 0.5);
+    }
+
+    #[test]
+    fn shadows_carry_the_casting_shape() {
+        let shadow = Shadow::new(3.0,4.0,8.0).spread(2.0);
+        let pie = Shape::fill_pie(10.0,20.0,30.0,40.0,0.5,PI).transform([2.0,0.0,0.0,1.0]);
+        let cast = Shape::shadow(&pie,&shadow).unwrap();
+        assert_eq!(cast.kind, ShapeKind::Shadow.id());
+        assert_eq!([cast.x,cast.y,cast.width,cast.height], [13.0,24.0,30.0,40.0]);
+        assert_eq!(cast.params, [8.0,2.0,0.0,0.0]);
+        assert_eq!(cast.data[0], ShapeKind::FillPie.id().code());
+        assert_eq!(cast.dash, pie.params);
+        assert_eq!(cast.transform, pie.transform);
+
+        let polygon = Shape::fill_polygon(0.0,0.0,10.0,10.0,7,5);
+        let cast = Shape::shadow(&polygon,&shadow).unwrap();
+        assert_eq!(&cast.data[..3], &[ShapeKind::FillPolygon.id().code(),7,5]);
+
+        let round = Shape::fill_round_rect(0.0,0.0,10.0,10.0,3.0);
+        assert_eq!(Shape::shadow(&round,&shadow).unwrap().params[2], 3.0);
+    }
+
+    #[test]
+    fn ring_inner_radius_is_clamped() {
+        assert_eq!(Shape::ring(0.0,0.0,10.0,10.0,-1.0).params[2], 0.0);
+        assert_eq!(Shape::ring(0.0,0.0,10.0,10.0,2.0).params[2], 1.0);
+    }
+}
@@ -1,13 +1,165 @@
 // Beginnings of a library of shaping functions. Or so.
-fn border(st: vec2<f32>, t: vec2<f32>) -> f32 {
-    var tl = step(t,st);
-    var br = step(t,1.0 - st);
-    return tl.x * tl.y * br.x * br.y;
+// KIND_ names are the built in ShapeKinds, replaced with their numbers by ShapeRegistry::shader_source.
+// Distances are in pixels from the shape's center, negative inside.
+fn box_dist(p: vec2<f32>, r: vec2<f32>) -> f32 {
+    let d = abs(p) - r;
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
 }
-fn oval(st: vec2<f32>) -> f32 {
-    var pct = distance(vec2<f32>(0.5,0.5),st);
-    pct = smoothstep(0.0,0.05,1.0 - pct);
-    return pct;
+fn round_box_dist(p: vec2<f32>, r: vec2<f32>, corner: f32) -> f32 {
+    let radius = clamp(corner, 0.0, min(r.x, r.y));
+    return box_dist(p, r - radius) - radius;
+}
+// Closest point on the ellipse by iterating on its evolute, three steps is plenty.
+fn ellipse_dist(p: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let q = abs(p);
+    let r = max(radii, vec2<f32>(0.0001));
+    var t = vec2<f32>(0.70710678, 0.70710678);
+    for (var i = 0; i < 3; i++) {
+        let e = vec2<f32>((r.x * r.x - r.y * r.y) / r.x, (r.y * r.y - r.x * r.x) / r.y) * t * t * t;
+        let rv = r * t - e;
+        let qv = q - e;
+        let next = clamp((qv * length(rv) / max(length(qv), 0.0001) + e) / r, vec2<f32>(0.0), vec2<f32>(1.0));
+        if (length(next) > 0.0001) { // at the center of a circle every point is closest, keep the last guess
+            t = next / length(next);
+        }
+    }
+    let d = distance(q, r * t);
+    if (dot(q / r, q / r) < 1.0) {
+        return -d;
+    }
+    return d;
+}
+// Distance to the wedge between two angles, negative inside. Angles go clockwise from +x.
+fn wedge_dist(p: vec2<f32>, start: f32, end: f32) -> f32 {
+    let half = (end - start) / 2.0;
+    if (half >= 3.1415927) {
+        return -1.0e6;
+    }
+    let mid = start + half;
+    var q = vec2<f32>(cos(mid) * p.x + sin(mid) * p.y, cos(mid) * p.y - sin(mid) * p.x);
+    q.y = abs(q.y);
+    let c = vec2<f32>(cos(half), sin(half));
+    let m = length(q - c * max(dot(q, c), 0.0));
+    return m * sign(c.x * q.y - c.y * q.x);
+}
+// Pie slice, or ring segment when inner > 0.
+fn arc_dist(p: vec2<f32>, r: vec2<f32>, params: vec4<f32>) -> f32 {
+    var d = max(ellipse_dist(p, r), wedge_dist(p, params.x, params.y));
+    if (params.z > 0.0) {
+        d = max(d, -ellipse_dist(p, r * params.z));
+    }
+    return d;
+}
+fn stroke(d: f32, thickness: f32) -> f32 {
+    return abs(d + thickness / 2.0) - thickness / 2.0;
+}
+// Distance along the center line of a rect outline, clockwise from the top left corner.
+fn rect_length(p: vec2<f32>, c: vec2<f32>) -> f32 {
+    if (c.y - abs(p.y) < c.x - abs(p.x)) {
+        if (p.y < 0.0) {
+            return p.x + c.x;
+        }
+        return 3.0 * c.x + 2.0 * c.y - p.x;
+    }
+    if (p.x > 0.0) {
+        return 2.0 * c.x + c.y + p.y;
+    }
+    return 4.0 * c.x + 3.0 * c.y - p.y;
+}
+// Distance along a round rect outline, clockwise from where the top edge leaves the top left corner.
+// Matches rect_length when the corners are square.
+fn round_rect_length(p: vec2<f32>, c: vec2<f32>, corner: f32) -> f32 {
+    let radius = clamp(corner, 0.0, min(c.x, c.y));
+    let h = c - radius;
+    let q = abs(p) - h;
+    let turn = radius * 1.5707963;
+    let top = 2.0 * h.x;
+    let side = 2.0 * h.y;
+    if (q.x > 0.0 && q.y > 0.0) { // corners, each a quarter turn around its center
+        if (p.x > 0.0 && p.y < 0.0) {
+            return top + radius * (atan2(q.x, q.y));
+        }
+        if (p.x > 0.0) {
+            return top + turn + side + radius * atan2(q.y, q.x);
+        }
+        if (p.y > 0.0) {
+            return 2.0 * top + 2.0 * turn + side + radius * atan2(q.x, q.y);
+        }
+        return 2.0 * top + 3.0 * turn + 2.0 * side + radius * atan2(q.y, q.x);
+    }
+    if (c.y - abs(p.y) < c.x - abs(p.x)) {
+        if (p.y < 0.0) {
+            return p.x + h.x;
+        }
+        return top + 2.0 * turn + side + h.x - p.x;
+    }
+    if (p.x > 0.0) {
+        return top + turn + p.y + h.y;
+    }
+    return 2.0 * top + 3.0 * turn + side + h.y - p.y;
+}
+// Length of an ellipse from parametric angle 0 to a, for a up to a quarter turn. Simpson's rule
+// over the speed of the parametric point, which is smooth, so a few steps are accurate to a fraction of a pixel.
+fn ellipse_arc(a: f32, r: vec2<f32>) -> f32 {
+    let n = 16;
+    let h = a / f32(n);
+    var sum = r.y + length(vec2<f32>(r.x * sin(a), r.y * cos(a)));
+    for (var i = 1; i < n; i++) {
+        let t = h * f32(i);
+        let w = select(2.0, 4.0, i % 2 == 1);
+        sum += w * length(vec2<f32>(r.x * sin(t), r.y * cos(t)));
+    }
+    return sum * h / 3.0;
+}
+// Distance along an ellipse, clockwise from +x. The speed is symmetric in each quarter, so only
+// part of one quarter is integrated.
+fn ellipse_length(p: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let r = max(radii, vec2<f32>(0.0001));
+    var a = atan2(p.y / r.y, p.x / r.x);
+    if (a < 0.0) {
+        a += 6.2831853;
+    }
+    let quarter = floor(min(a / 1.5707963, 3.0));
+    let within = a - quarter * 1.5707963;
+    let whole = ellipse_arc(1.5707963, r);
+    if (i32(quarter) % 2 == 0) {
+        return quarter * whole + ellipse_arc(within, r);
+    }
+    return (quarter + 1.0) * whole - ellipse_arc(1.5707963 - within, r);
+}
+// Cuts a stroke with distance d into dashes. along is the fragment's position along the stroke,
+// center its distance from the stroke's center line. dash is (dash, gap, offset, start).
+fn dashed(d: f32, along: f32, center: f32, thickness: f32, dash: vec4<f32>, round: bool) -> f32 {
+    let period = dash.x + dash.y;
+    if (period <= 0.0) {
+        return d;
+    }
+    let s = along + dash.w - dash.z - dash.x / 2.0 + period / 2.0;
+    let u = s - period * floor(s / period) - period / 2.0;
+    let a = abs(u) - dash.x / 2.0;
+    if (round) {
+        return length(vec2<f32>(max(a, 0.0), center)) - thickness / 2.0;
+    }
+    return max(d, a);
+}
+fn erf(x: f32) -> f32 {
+    let a = abs(x);
+    var r = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    r = r * r;
+    return sign(x) * (1.0 - 1.0 / (r * r));
+}
+// Coverage of a shape at distance d convolved with a gaussian, blur is twice its deviation.
+fn shadow(d: f32, blur: f32, pixel: f32) -> f32 {
+    let sigma = max(blur / 2.0, pixel * 0.5);
+    return 0.5 - 0.5 * erf(d / (sigma * 1.4142136));
+}
+// pixel is how much the distance changes across one screen pixel.
+fn coverage(d: f32, pixel: f32) -> f32 {
+    return clamp(0.5 - d / pixel, 0.0, 1.0);
+}
+// Blending isn't premultiplied, so coverage only scales alpha.
+fn fade(color: vec4<f32>, amount: f32) -> vec4<f32> {
+    return vec4<f32>(color.rgb, color.a * amount);
 }
 struct VertexInput {
     @location(0) pos: vec2<f32>,
@@ -16,95 +168,306 @@ struct VertexInput {
     @location(3) angle: f32,
     @location(4) kind: i32,
     @location(5) thickness: f32,
+    @location(6) params: vec4<f32>,
+    @location(7) paint: vec4<i32>,
+    @location(8) gradient: vec4<f32>,
+    @location(9) data: vec4<i32>,
+    @location(10) dash: vec4<f32>,
+    @location(11) transform: vec4<f32>,
+    @location(12) uv: vec4<f32>,
+    @location(13) tiling: vec4<f32>,
 }
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
-    @location(1) pos: vec2<f32>,
+    @location(1) local: vec2<f32>,
     @location(2) size: vec2<f32>,
     @location(3) kind: i32,
     @location(4) tex_coords: vec2<f32>,
     @location(5) thickness: f32,
+    @location(6) params: vec4<f32>,
+    @location(7) paint: vec4<i32>,
+    @location(8) gradient: vec4<f32>,
+    @location(9) data: vec4<i32>,
+    @location(10) dash: vec4<f32>,
+    @location(11) transform: vec4<f32>,
+    @location(12) uv: vec4<f32>,
+    @location(13) tiling: vec4<f32>,
 };
 @group(0) @binding(0)
 var<uniform> screen: vec2<f32>;
 @group(0) @binding(1)
 var<uniform> timer: f32;
+struct Stop {
+    color: vec4<f32>,
+    offset: f32,
+}
+@group(0) @binding(2)
+var<storage, read> stops: array<Stop>;
+@group(0) @binding(3)
+var<storage, read> points: array<vec2<f32>>;
 @group(1) @binding(0)
 var t: texture_2d<f32>;
 @group(1) @binding(1)
 var s: sampler;
 
+// Quads are padded so edges have room for anti-aliasing.
+let PADDING: f32 = 1.0;
+
 @vertex
 fn vs_main(@builtin(vertex_index) index: u32, in: VertexInput) -> VertexOutput {
-    let rotation = mat2x2<f32>(cos(in.angle),-sin(in.angle),sin(in.angle),cos(in.angle));
-    let size = vec2<f32>(in.size.x,in.size.y);
-    let position = vec2<f32>(in.pos.x,in.pos.y) + size/2.0;
-    let translation = (position/screen);
-    let translation = (vec2<f32>(translation.x,1.0 - translation.y) - 0.5) * 2.0;
-    var p = vec2<f32>(0.0,0.0);
+    let rotation = mat2x2<f32>(cos(in.angle),sin(in.angle),-sin(in.angle),cos(in.angle));
+    let transform = mat2x2<f32>(in.transform.xy,in.transform.zw);
+    var padding = PADDING;
+    if (in.kind == KIND_SHADOW) { // shadows reach three deviations past the shape
+        padding += in.params.x * 1.5 + max(in.params.y, 0.0);
+    }
+    let size = vec2<f32>(in.size.x,in.size.y) + padding * 2.0;
+    let center = vec2<f32>(in.pos.x,in.pos.y) + in.size/2.0;
+    var corner = vec2<f32>(0.0,0.0);
     var out: VertexOutput;
-    switch (index) {  // construct a triangle strip of two triangles from the index.
-        case 0u, 4u: {      // bottom left
-            p.x = -size.x;
-            p.y = -size.y;
-            out.tex_coords = vec2(0.0,0.0);
-        }
-        case 1u: {       //  top left
-            p.x = -size.x;
-            p.y = size.y;
-            out.tex_coords = vec2(0.0,1.0);
-        }
-        case 2u: {      // top right
-            p.x = size.x;
-            p.y = size.y;
-            out.tex_coords = vec2(1.0,1.0);
-        }
-        case 3u: {    // bottom right
-            p.x = size.x;
-            p.y = -size.y;
-            out.tex_coords = vec2(1.0,0.0);
+    switch (index) {  // construct a triangle strip of two triangles from the index, covering each pixel once.
+        case 0u: {      // top left
+            corner = vec2<f32>(-1.0,-1.0);
+        }
+        case 1u: {       //  bottom left
+            corner = vec2<f32>(-1.0,1.0);
+        }
+        case 2u: {    // top right
+            corner = vec2<f32>(1.0,-1.0);
+        }
+        case 3u: {      // bottom right
+            corner = vec2<f32>(1.0,1.0);
         }
         default: {}
     }
-    p.y = -p.y;
-    out.clip_position = vec4<f32>((rotation * p) / screen + translation,0.0,1.0);
-    out.pos = in.pos;
+    out.local = corner * size / 2.0;
+    out.tex_coords = out.local / max(in.size, vec2<f32>(0.0001)) + 0.5;
+    let world = center + transform * (rotation * out.local); // pixels, y down
+    out.clip_position = vec4<f32>(world.x / screen.x * 2.0 - 1.0, 1.0 - world.y / screen.y * 2.0, 0.0, 1.0);
     out.size = in.size;
     out.kind =  in.kind;
     out.color = in.color;
     out.thickness = in.thickness;
+    out.params = in.params;
+    out.paint = in.paint;
+    out.gradient = in.gradient;
+    out.data = in.data;
+    out.dash = in.dash;
+    out.uv = in.uv;
+    out.tiling = in.tiling;
     return out;
 }
 
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var thickness = vec2<f32>(in.thickness / in.size.x , in.thickness / in.size.y) / 2.0;
-    var diffuse_color = textureSample(t,s,in.tex_coords);
-    var ndcPos = in.tex_coords * 2.0 - 0.5; // convert to -1,1 range for some functions
-    if (in.kind == 0) {
-        return in.color;
-    } // filled rect just for completeness.
-    if (in.kind == 1) { // filled oval
-        return in.color * oval(ndcPos);
+// Distance to a polygon stored in points, negative inside (even-odd).
+fn polygon_dist(p: vec2<f32>, first: i32, count: i32) -> f32 {
+    var d = dot(p - points[first], p - points[first]);
+    var s = 1.0;
+    var j = count - 1;
+    for (var i = 0; i < count; i++) {
+        let vi = points[first + i];
+        let vj = points[first + j];
+        let e = vj - vi;
+        let w = p - vi;
+        let b = w - e * clamp(dot(w, e) / max(dot(e, e), 0.0001), 0.0, 1.0);
+        d = min(d, dot(b, b));
+        let c = vec3<bool>(p.y >= vi.y, p.y < vj.y, e.x * w.y > e.y * w.x);
+        if (all(c) || !any(c)) {
+            s = -s;
+        }
+        j = i;
     }
-    if (in.kind == 2) { // rect outline
-        var pct = 1.0 - border(in.tex_coords,thickness);
-        return in.color * pct;
+    return s * sqrt(d);
+}
+// Unsigned distance to connected segments.
+fn polyline_dist(p: vec2<f32>, first: i32, count: i32) -> f32 {
+    var d = dot(p - points[first], p - points[first]);
+    for (var i = 1; i < count; i++) {
+        let a = points[first + i - 1];
+        let e = points[first + i] - a;
+        let w = p - a;
+        let b = w - e * clamp(dot(w, e) / max(dot(e, e), 0.0001), 0.0, 1.0);
+        d = min(d, dot(b, b));
     }
-    if (in.kind == 3) { // oval outline
-        var d = distance(vec2<f32>(0.5,0.5),ndcPos);
-        pct = step(thickness.x,1.0 - d);
-        pct *= step(0.9 - thickness.x,d);
-        return in.color * pct;
+    return sqrt(d);
+}
+// Distance to the shape casting a shadow, grown by spread. source is the caster's kind, first point
+// and point count, shape its params.
+fn caster_dist(p: vec2<f32>, r: vec2<f32>, spread: f32, corner: f32, source: vec4<i32>, shape: vec4<f32>) -> f32 {
+    var d = 0.0;
+    switch (source.x) {
+        case KIND_FILL_OVAL, KIND_TEX_OVAL: { // ovals
+            d = ellipse_dist(p, r + spread);
+        }
+        case KIND_FILL_PIE, KIND_FILL_RING: { // pies and rings
+            d = arc_dist(p, r, shape) - spread;
+        }
+        case KIND_FILL_POLYGON: { // polygons
+            d = polygon_dist(p, source.y, source.z) - spread;
+        }
+        default: { // rects, round rects and images
+            d = round_box_dist(p, r + spread, corner + spread);
+        }
     }
-    if (in.kind == 4) { // textured rect
-        return in.color * diffuse_color;
+    return d;
+}
+// Texture coordinates for a nine slice, insets are the texture pixels at (left, top, right, bottom)
+// that keep their size. Corners shrink evenly when the shape is smaller than them.
+fn nine_slice(p: vec2<f32>, size: vec2<f32>, insets: vec4<f32>, region: vec2<f32>) -> vec2<f32> {
+    let k = clamp(size / max(insets.xy + insets.zw, vec2<f32>(0.0001)), vec2<f32>(0.0001), vec2<f32>(1.0));
+    let lo = insets.xy * k;
+    let hi = insets.zw * k;
+    let q = p + size / 2.0; // from the top left
+    let start = q / k;
+    let end = region - (size - q) / k;
+    let middle = insets.xy + (q - lo) / max(size - lo - hi, vec2<f32>(0.0001)) * (region - insets.xy - insets.zw);
+    let texel = select(select(middle, end, q > size - hi), start, q < lo);
+    return texel / max(region, vec2<f32>(0.0001));
+}
+// Wraps coordinates into 0..1, clamped, repeated or mirrored. This happens here instead of in the
+// sampler so a region of a texture can tile too.
+fn wrap(c: vec2<f32>, mode: i32) -> vec2<f32> {
+    if (mode == 1) {
+        return fract(c);
     }
-    if (in.kind == 5) { // textured oval
-        return diffuse_color * in.color * oval(ndcPos);
+    if (mode == 2) {
+        return 1.0 - abs(c - 2.0 * floor(c / 2.0) - 1.0);
     }
-    return vec4<f32>(1.0,0.0,1.0,1.0);
+    return clamp(c, vec2<f32>(0.0), vec2<f32>(1.0));
 }
+// ENCODE SRGB
+// Solid color, or the gradient sampled at this fragment tinted by the color.
+fn paint(in: VertexOutput) -> vec4<f32> {
+    if (in.paint.x == 0) {
+        return in.color;
+    }
+    var f = 0.0;
+    if (in.paint.x == 1) { // linear, spans the bounds along the direction
+        let local = in.local;
+        let extent = abs(in.size.x * in.gradient.x) + abs(in.size.y * in.gradient.y);
+        f = dot(local, in.gradient.xy) / max(extent, 0.0001) + 0.5;
+    } else { // radial
+        f = distance(in.tex_coords, in.gradient.xy) / in.gradient.z;
+    }
+    f = clamp(f, 0.0, 1.0);
+    let first = in.paint.y;
+    var color = stops[first].color;
+    for (var i = 1; i < in.paint.z; i++) {
+        let a = stops[first + i - 1];
+        let b = stops[first + i];
+        if (f > a.offset) {
+            color = mix(a.color, b.color, clamp((f - a.offset) / max(b.offset - a.offset, 0.0001), 0.0, 1.0));
+        }
+    }
+    return color * in.color;
+}
+
+// Everything a shape kind needs to color a fragment.
+struct Fragment {
+    local: vec2<f32>, // pixels from the center
+    radii: vec2<f32>,
+    pixel: f32,
+    color: vec4<f32>, // the fill color or gradient
+    texel: vec4<f32>, // the shape's texture sampled here
+}
+
+// CUSTOM SHAPE FUNCTIONS
 
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var f: Fragment;
+    f.color = paint(in);
+    // texture coordinates go 0..1 over the uv region, texels keeps them half a texel inside it
+    let texels = vec2<f32>(textureDimensions(t)) * abs(in.uv.zw - in.uv.xy);
+    var tex_coords = in.tex_coords * in.tiling.zw + in.tiling.xy;
+    if (in.kind == KIND_NINE_SLICE) {
+        tex_coords = nine_slice(in.local, in.size, in.params, texels);
+    }
+    let edge = 0.5 / max(texels, vec2<f32>(1.0));
+    let wrapped = clamp(wrap(tex_coords, in.data.w), edge, 1.0 - edge);
+    let extent = in.uv.zw - in.uv.xy;
+    f.texel = encode_srgb(textureSampleGrad(t,s,in.uv.xy + wrapped * extent,dpdx(tex_coords) * extent,dpdy(tex_coords) * extent));
+    f.pixel = max((length(dpdx(in.local)) + length(dpdy(in.local))) / 2.0, 0.0001);
+    f.local = in.local;
+    f.radii = in.size / 2.0;
+    let local = f.local;
+    let radii = f.radii;
+    let color = f.color;
+    let pixel = f.pixel;
+    let round = in.data.z != 0;
+    let half = in.thickness / 2.0;
+    switch (in.kind) {
+        case KIND_FILL_RECT: { // filled rect
+            return fade(color, coverage(box_dist(local, radii), pixel));
+        }
+        case KIND_FILL_OVAL: { // filled oval
+            return fade(color, coverage(ellipse_dist(local, radii), pixel));
+        }
+        case KIND_RECT: { // rect outline
+            let b = box_dist(local, radii);
+            let d = dashed(stroke(b, in.thickness), rect_length(local, radii - half), b + half, in.thickness, in.dash, round);
+            return fade(color, coverage(d, pixel));
+        }
+        case KIND_OVAL: { // oval outline
+            let e = ellipse_dist(local, radii);
+            let d = dashed(stroke(e, in.thickness), ellipse_length(local, radii - half), e + half, in.thickness, in.dash, round);
+            return fade(color, coverage(d, pixel));
+        }
+        case KIND_TEX_RECT: { // textured rect
+            return fade(color * f.texel, coverage(box_dist(local, radii), pixel));
+        }
+        case KIND_TEX_OVAL: { // textured oval
+            return fade(f.texel * color, coverage(ellipse_dist(local, radii), pixel));
+        }
+        case KIND_ARC: { // arc, dashes start at its start angle
+            let e = ellipse_dist(local, radii);
+            let center = radii - half;
+            let start = ellipse_length(vec2<f32>(cos(in.params.x), sin(in.params.x)), center);
+            var along = ellipse_length(local, center) - start;
+            if (along < 0.0) {
+                along += 4.0 * ellipse_arc(1.5707963, max(center, vec2<f32>(0.0001)));
+            }
+            let d = max(dashed(stroke(e, in.thickness), along, e + half, in.thickness, in.dash, round), wedge_dist(local, in.params.x, in.params.y));
+            return fade(color, coverage(d, pixel));
+        }
+        case KIND_FILL_PIE, KIND_FILL_RING: { // filled pie, filled ring
+            return fade(color, coverage(arc_dist(local, radii, in.params), pixel));
+        }
+        case KIND_PIE, KIND_RING: { // pie outline, ring outline
+            return fade(color, coverage(stroke(arc_dist(local, radii, in.params), in.thickness), pixel));
+        }
+        case KIND_LINE: { // line, a capsule along the x axis
+            let d = length(vec2<f32>(max(abs(local.x) - (radii.x - half), 0.0), local.y)) - half;
+            return fade(color, coverage(dashed(d, local.x + radii.x - half, local.y, in.thickness, in.dash, round), pixel));
+        }
+        case KIND_FILL_POLYGON: { // filled polygon
+            return fade(color, coverage(polygon_dist(local, in.data.x, in.data.y), pixel));
+        }
+        case KIND_POLYGON: { // polygon outline
+            return fade(color, coverage(stroke(polygon_dist(local, in.data.x, in.data.y), in.thickness), pixel));
+        }
+        case KIND_FILL_ROUND_RECT: { // filled round rect
+            return fade(color, coverage(round_box_dist(local, radii, in.params.x), pixel));
+        }
+        case KIND_ROUND_RECT: { // round rect outline
+            let b = round_box_dist(local, radii, in.params.x);
+            let along = round_rect_length(local, radii - half, in.params.x - half);
+            return fade(color, coverage(dashed(stroke(b, in.thickness), along, b + half, in.thickness, in.dash, round), pixel));
+        }
+        case KIND_SHADOW: { // shadow
+            let d = caster_dist(local, radii, in.params.y, in.params.z, in.data, in.dash);
+            return fade(color, shadow(d, in.params.x, pixel));
+        }
+        case KIND_NINE_SLICE: { // nine slice
+            return fade(color * f.texel, coverage(box_dist(local, radii), pixel));
+        }
+        case KIND_POLYLINE: { // polyline
+            return fade(color, coverage(polyline_dist(local, in.data.x, in.data.y) - half, pixel));
+        }
+        // CUSTOM SHAPE KINDS
+        default: {
+            return vec4<f32>(1.0,0.0,1.0,1.0);
+        }
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use crate::json::Json;
+use crate::shape::Rect;
+
+// Layout of a sheet of equally sized frames, in pixels. margin is around the whole sheet,
+// spacing between neighbouring frames.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Grid {
+    pub frame_width: f32,
+    pub frame_height: f32,
+    pub margin: f32,
+    pub spacing: f32,
+}
+
+impl Grid {
+    pub fn new(frame_width: f32, frame_height: f32) -> Self {
+        Self { frame_width, frame_height, margin: 0.0, spacing: 0.0 }
+    }
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+}
+
+// A region of a sprite sheet's image, and how it sits in the sprite it was cut from.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Frame {
+    // Where the pixels are in the image. Rotated frames are stored turned a quarter clockwise,
+    // so the region is as wide as the sprite is tall.
+    pub rect: Rect,
+    pub rotated: bool,
+    // Size of the sprite before it was trimmed, and where the trimmed pixels go in it.
+    pub size: [f32; 2],
+    pub offset: [f32; 2],
+    // Point drawn at the position given to Graphics::sprite, 0..1 of size.
+    pub pivot: [f32; 2],
+}
+
+impl Frame {
+    pub fn new(rect: Rect) -> Self {
+        Self { rect, rotated: false, size: [rect.width, rect.height], offset: [0.0, 0.0], pivot: [0.0, 0.0] }
+    }
+    pub fn pivot(mut self, x: f32, y: f32) -> Self {
+        self.pivot = [x, y];
+        self
+    }
+}
+
+// Frames are regions of one image, numbered in the order they were added. Grid frames go
+// row by row from the top left.
+#[derive(Clone,Debug,Default)]
+pub struct SpriteSheet {
+    pub image: String,
+    frames: Vec<Frame>,
+    names: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    pub fn new(image: &str) -> Self {
+        Self { image: image.to_string(), frames: vec![], names: HashMap::new() }
+    }
+    // Every whole frame that fits in an image of image_width by image_height.
+    pub fn grid(image: &str, image_width: f32, image_height: f32, grid: Grid) -> Self {
+        let mut sheet = Self::new(image);
+        if grid.frame_width <= 0.0 || grid.frame_height <= 0.0 {
+            return sheet;
+        }
+        let count = |size: f32, frame: f32| ((size - grid.margin * 2.0 + grid.spacing) / (frame + grid.spacing)).floor().max(0.0) as usize;
+        let (columns, rows) = (count(image_width, grid.frame_width), count(image_height, grid.frame_height));
+        for row in 0..rows {
+            for column in 0..columns {
+                sheet.frames.push(Frame::new(Rect::new(
+                    grid.margin + column as f32 * (grid.frame_width + grid.spacing),
+                    grid.margin + row as f32 * (grid.frame_height + grid.spacing),
+                    grid.frame_width,
+                    grid.frame_height,
+                )));
+            }
+        }
+        sheet
+    }
+    // Adds a frame, a name already in use moves to the new frame.
+    pub fn frame(mut self, name: &str, rect: Rect) -> Self {
+        self.add_frame(name, rect);
+        self
+    }
+    pub fn add_frame(&mut self, name: &str, rect: Rect) -> usize {
+        self.add(name, Frame::new(rect))
+    }
+    pub fn add(&mut self, name: &str, frame: Frame) -> usize {
+        self.frames.push(frame);
+        self.names.insert(name.to_string(), self.frames.len() - 1);
+        self.frames.len() - 1
+    }
+    // Names a frame that is already there, like one from a grid.
+    pub fn name(&mut self, name: &str, index: usize) {
+        self.names.insert(name.to_string(), index);
+    }
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+    pub fn get(&self, index: usize) -> Option<&Frame> {
+        self.frames.get(index)
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Frame> {
+        self.frames.get_mut(index)
+    }
+    pub fn rect(&self, index: usize) -> Option<Rect> {
+        self.frames.get(index).map(|f| f.rect)
+    }
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+// A sheet and its animations, as exported by TexturePacker or Aseprite.
+#[derive(Clone,Debug,Default)]
+pub struct SpriteData {
+    pub sheet: SpriteSheet,
+    // By frame tag name, Aseprite only.
+    pub animations: HashMap<String, Animation>,
+}
+
+impl SpriteData {
+    // Seconds per frame when the json has no durations.
+    pub const DEFAULT_DURATION: f32 = 0.1;
+
+    // Parses the json hash or json array export of either tool. The sheet's image is meta.image as
+    // written, Graphics::load_sprite_data makes it relative to the json file.
+    pub fn parse(json: &str) -> Result<SpriteData> {
+        let json = Json::parse(json)?;
+        let frames: Vec<(String, &Json)> = match json.get("frames") {
+            Json::Object(pairs) => pairs.iter().map(|(name, frame)| (name.clone(), frame)).collect(),
+            Json::Array(frames) => frames.iter()
+                .enumerate()
+                .map(|(i, frame)| (frame.get("filename").as_str().map_or_else(|| i.to_string(), String::from), frame))
+                .collect(),
+            _ => return Err(anyhow!("Sprite json has no frames")),
+        };
+        let meta = json.get("meta");
+        let mut sheet = SpriteSheet::new(meta.get("image").as_str().unwrap_or_default());
+        let mut durations = vec![];
+        for (name, json) in frames {
+            let rect = json_rect(json.get("frame")).ok_or_else(|| anyhow!("Sprite frame {} has no frame rectangle", name))?;
+            let rotated = json.get("rotated").as_bool().unwrap_or(false);
+            let trimmed = json_rect(json.get("spriteSourceSize"));
+            let source = json.get("sourceSize");
+            let mut frame = Frame::new(if rotated { Rect::new(rect.x, rect.y, rect.height, rect.width) } else { rect });
+            frame.rotated = rotated;
+            frame.size = [source.get("w").as_f32().unwrap_or(rect.width), source.get("h").as_f32().unwrap_or(rect.height)];
+            frame.offset = trimmed.map_or([0.0, 0.0], |t| [t.x, t.y]);
+            frame.pivot = [json.get("pivot").get("x").as_f32().unwrap_or(0.0), json.get("pivot").get("y").as_f32().unwrap_or(0.0)];
+            sheet.add(&name, frame);
+            durations.push(json.get("duration").as_f32().map_or(Self::DEFAULT_DURATION, |ms| ms / 1000.0));
+        }
+        // Aseprite slice pivots are in pixels and hold from their key's frame on.
+        for slice in meta.get("slices").as_array().unwrap_or_default() {
+            let keys = slice.get("keys").as_array().unwrap_or_default();
+            for (i, key) in keys.iter().enumerate() {
+                let (Some(bounds), Some(x), Some(y)) = (json_rect(key.get("bounds")), key.get("pivot").get("x").as_f32(), key.get("pivot").get("y").as_f32()) else {
+                    continue;
+                };
+                let first = key.get("frame").as_f32().unwrap_or(0.0) as usize;
+                let last = keys.get(i + 1).and_then(|k| k.get("frame").as_f32()).map_or(usize::MAX, |f| f as usize);
+                for frame in sheet.frames.iter_mut().take(last).skip(first) {
+                    frame.pivot = [(bounds.x + x) / frame.size[0].max(1.0), (bounds.y + y) / frame.size[1].max(1.0)];
+                }
+            }
+        }
+        let mut animations = HashMap::new();
+        for tag in meta.get("frameTags").as_array().unwrap_or_default() {
+            let name = tag.get("name").as_str().unwrap_or_default();
+            let from = tag.get("from").as_f32().unwrap_or(0.0) as usize;
+            let to = tag.get("to").as_f32().unwrap_or(0.0) as usize;
+            let mut frames: Vec<(usize, f32)> = (from..=to).filter(|f| *f < sheet.len()).map(|f| (f, durations[f])).collect();
+            let direction = tag.get("direction").as_str().unwrap_or("forward");
+            if direction == "reverse" || direction == "pingpong_reverse" {
+                frames.reverse();
+            }
+            // repeat is a string, and only once or forever are played as asked
+            let playback = match (direction, tag.get("repeat").as_str()) {
+                (_, Some("1")) => Playback::Once,
+                ("pingpong" | "pingpong_reverse", _) => Playback::PingPong,
+                _ => Playback::Loop,
+            };
+            animations.insert(name.to_string(), Animation { frames, playback });
+        }
+        Ok(SpriteData { sheet, animations })
+    }
+}
+
+// {"x","y","w","h"}
+fn json_rect(json: &Json) -> Option<Rect> {
+    Some(Rect::new(json.get("x").as_f32()?, json.get("y").as_f32()?, json.get("w").as_f32()?, json.get("h").as_f32()?))
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Playback {
+    // Stops on the last frame.
+    Once,
+    Loop,
+    // Forwards then backwards, without showing the end frames twice.
+    PingPong,
+}
+
+// Frames of a sprite sheet and how long each shows, in seconds.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Animation {
+    pub frames: Vec<(usize, f32)>,
+    pub playback: Playback,
+}
+
+impl Animation {
+    pub fn new(playback: Playback) -> Self {
+        Self { frames: vec![], playback }
+    }
+    // The frames first to last, each shown for seconds.
+    pub fn range(frames: std::ops::Range<usize>, seconds: f32, playback: Playback) -> Self {
+        Self { frames: frames.map(|f| (f, seconds)).collect(), playback }
+    }
+    // Frames by name, names the sheet doesn't have are skipped.
+    pub fn named(sheet: &SpriteSheet, names: &[&str], seconds: f32, playback: Playback) -> Self {
+        Self { frames: names.iter().filter_map(|n| sheet.index(n)).map(|f| (f, seconds)).collect(), playback }
+    }
+    pub fn frame(mut self, index: usize, seconds: f32) -> Self {
+        self.frames.push((index, seconds));
+        self
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum AnimationEvent {
+    // The sheet frame now showing.
+    FrameChanged(usize),
+    // Back at the start of a looping or ping pong animation.
+    Looped,
+    // A Once animation reached the end of its last frame.
+    Finished,
+}
+
+// Where an animation is, advanced by update on Event::Update.
+#[derive(Clone,Debug)]
+pub struct AnimationPlayer {
+    pub animation: Animation,
+    // 2.0 plays twice as fast.
+    pub speed: f32,
+    pub playing: bool,
+    step: usize,
+    time: f32,
+    forward: bool,
+    finished: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(animation: Animation) -> Self {
+        Self { animation, speed: 1.0, playing: true, step: 0, time: 0.0, forward: true, finished: false }
+    }
+    // Plays another animation from its start, unless it is the one already playing.
+    pub fn play(&mut self, animation: &Animation) {
+        if self.animation != *animation {
+            self.animation = animation.clone();
+            self.restart();
+        }
+        self.playing = true;
+    }
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.time = 0.0;
+        self.forward = true;
+        self.finished = false;
+    }
+    // The sheet frame to draw.
+    pub fn frame(&self) -> usize {
+        self.animation.frames.get(self.step).map_or(0, |(f, _)| *f)
+    }
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    // Moves on by delta, returns what happened on the way, in order.
+    pub fn update(&mut self, delta: &Duration) -> Vec<AnimationEvent> {
+        let mut events = vec![];
+        let len = self.animation.frames.len();
+        if !self.playing || self.finished || len == 0 {
+            return events;
+        }
+        self.time += delta.as_secs_f32() * self.speed.max(0.0);
+        while !self.finished {
+            let seconds = self.animation.frames[self.step].1.max(0.0001);
+            if self.time < seconds {
+                break;
+            }
+            self.time -= seconds;
+            let before = self.frame();
+            match self.animation.playback {
+                Playback::Once => {
+                    if self.step + 1 < len {
+                        self.step += 1;
+                    } else {
+                        self.finished = true;
+                        self.time = 0.0;
+                        events.push(AnimationEvent::Finished);
+                    }
+                }
+                Playback::Loop => {
+                    self.step = (self.step + 1) % len;
+                    if self.step == 0 {
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+                Playback::PingPong if len > 1 => {
+                    if self.step + 1 == len {
+                        self.forward = false;
+                    } else if self.step == 0 {
+                        self.forward = true;
+                    }
+                    self.step = if self.forward { self.step + 1 } else { self.step - 1 };
+                    if self.step == 0 {
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+                Playback::PingPong => events.push(AnimationEvent::Looped),
+            }
+            if self.frame() != before {
+                events.push(AnimationEvent::FrameChanged(self.frame()));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = r#"{
+        "frames": {
+            "walk 0": {"frame": {"x": 0, "y": 0, "w": 8, "h": 10}, "rotated": false,
+                "spriteSourceSize": {"x": 0, "y": 0, "w": 8, "h": 10}, "sourceSize": {"w": 8, "h": 10}},
+            "walk 1": {"frame": {"x": 8, "y": 0, "w": 10, "h": 6}, "rotated": true,
+                "spriteSourceSize": {"x": 2, "y": 3, "w": 6, "h": 10}, "sourceSize": {"w": 12, "h": 16},
+                "pivot": {"x": 0.5, "y": 1}}
+        },
+        "meta": {"image": "walk.png"}
+    }"#;
+
+    const ARRAY: &str = r#"{
+        "frames": [
+            {"filename": "idle", "frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "duration": 100},
+            {"frame": {"x": 16, "y": 0, "w": 16, "h": 16}, "duration": 200},
+            {"filename": "jump", "frame": {"x": 32, "y": 0, "w": 16, "h": 16}, "duration": 300}
+        ],
+        "meta": {
+            "image": "hero.png",
+            "frameTags": [
+                {"name": "run", "from": 0, "to": 2, "direction": "pingpong"},
+                {"name": "land", "from": 1, "to": 2, "direction": "reverse", "repeat": "1"},
+                {"name": "past the end", "from": 2, "to": 9}
+            ],
+            "slices": [{"name": "feet", "keys": [
+                {"frame": 1, "bounds": {"x": 0, "y": 0, "w": 16, "h": 16}, "pivot": {"x": 8, "y": 12}}
+            ]}]
+        }
+    }"#;
+
+    #[test]
+    fn parses_hash_exports() {
+        let data = SpriteData::parse(HASH).unwrap();
+        let sheet = &data.sheet;
+        assert_eq!(sheet.image, "walk.png");
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.index("walk 1"), Some(1));
+        assert_eq!(sheet.get(0), Some(&Frame::new(Rect::new(0.0, 0.0, 8.0, 10.0))));
+        // rotated frames are stored turned, their rect is the turned region
+        let walk = sheet.get(1).unwrap();
+        assert!(walk.rotated);
+        assert_eq!(walk.rect, Rect::new(8.0, 0.0, 6.0, 10.0));
+        // trimmed, the pixels sit inside the untrimmed size
+        assert_eq!(walk.size, [12.0, 16.0]);
+        assert_eq!(walk.offset, [2.0, 3.0]);
+        assert_eq!(walk.pivot, [0.5, 1.0]);
+        assert!(data.animations.is_empty());
+    }
+
+    #[test]
+    fn parses_array_exports_with_tags() {
+        let data = SpriteData::parse(ARRAY).unwrap();
+        let sheet = &data.sheet;
+        assert_eq!(sheet.len(), 3);
+        // frames without a filename are named by their index
+        assert_eq!((sheet.index("idle"), sheet.index("1"), sheet.index("jump")), (Some(0), Some(1), Some(2)));
+        assert_eq!(data.animations["run"], Animation { frames: vec![(0, 0.1), (1, 0.2), (2, 0.3)], playback: Playback::PingPong });
+        assert_eq!(data.animations["land"], Animation { frames: vec![(2, 0.3), (1, 0.2)], playback: Playback::Once });
+        assert_eq!(data.animations["past the end"].frames, vec![(2, 0.3)]);
+        // slice pivots are in pixels and hold from their frame on
+        assert_eq!(sheet.get(0).unwrap().pivot, [0.0, 0.0]);
+        assert_eq!(sheet.get(1).unwrap().pivot, [0.5, 0.75]);
+        assert_eq!(sheet.get(2).unwrap().pivot, [0.5, 0.75]);
+    }
+
+    #[test]
+    fn rejects_bad_sprite_json() {
+        assert!(SpriteData::parse("{").is_err());
+        assert!(SpriteData::parse(r#"{"meta": {}}"#).is_err());
+        assert!(SpriteData::parse(r#"{"frames": {"a": {"frame": {"x": 0}}}}"#).is_err());
+        let empty = SpriteData::parse(r#"{"frames": []}"#).unwrap();
+        assert!(empty.sheet.is_empty());
+        assert_eq!(empty.sheet.image, "");
+    }
+
+    fn frames(player: &mut AnimationPlayer, steps: usize) -> Vec<usize> {
+        (0..steps).map(|_| {
+            player.update(&Duration::from_secs(1));
+            player.frame()
+        }).collect()
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let mut player = AnimationPlayer::new(Animation::new(Playback::Once).frame(3, 1.0).frame(4, 1.0));
+        assert_eq!(player.update(&Duration::from_secs(1)), vec![AnimationEvent::FrameChanged(4)]);
+        assert_eq!(player.update(&Duration::from_secs(5)), vec![AnimationEvent::Finished]);
+        assert!(player.is_finished());
+        assert_eq!(player.frame(), 4);
+        assert!(player.update(&Duration::from_secs(1)).is_empty());
+        player.restart();
+        assert_eq!(player.frame(), 3);
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn loop_wraps_around() {
+        let mut player = AnimationPlayer::new(Animation::range(0..2, 0.5, Playback::Loop));
+        assert_eq!(player.update(&Duration::from_secs(1)), vec![
+            AnimationEvent::FrameChanged(1),
+            AnimationEvent::Looped,
+            AnimationEvent::FrameChanged(0),
+        ]);
+        assert_eq!(frames(&mut player, 3), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn ping_pong_doesnt_repeat_the_end_frames() {
+        let mut player = AnimationPlayer::new(Animation::range(0..3, 1.0, Playback::PingPong));
+        assert_eq!(frames(&mut player, 6), vec![1, 2, 1, 0, 1, 2]);
+        let mut single = AnimationPlayer::new(Animation::range(5..6, 1.0, Playback::PingPong));
+        assert_eq!(single.update(&Duration::from_secs(1)), vec![AnimationEvent::Looped]);
+        assert_eq!(single.frame(), 5);
+    }
+
+    #[test]
+    fn speed_and_pausing() {
+        let mut player = AnimationPlayer::new(Animation::range(0..4, 1.0, Playback::Loop));
+        player.speed = 2.0;
+        assert_eq!(frames(&mut player, 1), vec![2]);
+        player.playing = false;
+        assert!(player.update(&Duration::from_secs(1)).is_empty());
+        assert_eq!(player.frame(), 2);
+    }
+
+    #[test]
+    fn play_restarts_only_other_animations() {
+        let walk = Animation::range(0..4, 1.0, Playback::Loop);
+        let mut player = AnimationPlayer::new(walk.clone());
+        player.update(&Duration::from_secs(2));
+        player.play(&walk);
+        assert_eq!(player.frame(), 2);
+        player.play(&Animation::range(10..12, 1.0, Playback::Loop));
+        assert_eq!(player.frame(), 10);
+    }
+}
@@ -0,0 +1,6 @@
+// Textures sample as linear color, shapes are drawn in sRGB values as given, so texels go back to sRGB.
+fn encode_srgb(c: vec4<f32>) -> vec4<f32> {
+    let rgb = max(c.rgb, vec3<f32>(0.0));
+    let high = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return vec4<f32>(select(high, rgb * 12.92, rgb <= vec3<f32>(0.0031308)), c.a);
+}
@@ -1,8 +1,12 @@
 use image::{EncodableLayout, GenericImageView};
 use anyhow::*;
+use std::collections::HashMap;
+use crate::paint::{TextureFormat, TextureOptions};
+use crate::shape::TextureHandle;
 
 #[derive(Debug)]
 pub struct TextureInfo {
+    pub(crate) texture: wgpu::Texture,
     pub(crate) bind_group: wgpu::BindGroup,
     pub(crate) path: String,
     pub(crate) alias: Option<String>,
@@ -10,6 +14,153 @@ pub struct TextureInfo {
     pub(crate) height: u32,
 }
 
+#[derive(Debug)]
+struct Slot<T> {
+    name: String,
+    // None once forgotten by Graphics::clear_texture_cache, until it's loaded again by name.
+    texture: Option<T>,
+    generation: u32,
+    // Graphics::load calls not yet matched by Graphics::release.
+    refs: u32,
+}
+
+// Textures by handle and by name. A name keeps its slot, and so its handle, when its texture is
+// replaced or forgotten, handles only go stale when the texture is unloaded. Generic so it can be
+// tested without a gpu.
+#[derive(Debug)]
+pub(crate) struct TextureStore<T = TextureInfo> {
+    slots: Vec<Slot<T>>,
+    names: HashMap<String, u32>,
+    free: Vec<u32>,
+}
+
+impl<T> Default for TextureStore<T> {
+    fn default() -> Self {
+        Self { slots: vec![], names: HashMap::new(), free: vec![] }
+    }
+}
+
+impl<T> TextureStore<T> {
+    fn slot(&self, handle: TextureHandle) -> Option<&Slot<T>> {
+        self.slots.get(handle.index as usize).filter(|slot| slot.generation == handle.generation)
+    }
+    fn slot_mut(&mut self, handle: TextureHandle) -> Option<&mut Slot<T>> {
+        self.slots.get_mut(handle.index as usize).filter(|slot| slot.generation == handle.generation)
+    }
+    pub(crate) fn get(&self, handle: TextureHandle) -> Option<&T> {
+        self.slot(handle).and_then(|slot| slot.texture.as_ref())
+    }
+    // The handle of a name, loaded or not.
+    pub(crate) fn handle(&self, name: &str) -> Option<TextureHandle> {
+        let index = *self.names.get(name)?;
+        Some(TextureHandle { index, generation: self.slots[index as usize].generation })
+    }
+    pub(crate) fn name(&self, handle: TextureHandle) -> Option<&str> {
+        self.slot(handle).map(|slot| slot.name.as_str())
+    }
+    pub(crate) fn is_loaded(&self, name: &str) -> bool {
+        self.handle(name).is_some_and(|handle| self.get(handle).is_some())
+    }
+    // Names of the loaded textures.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &String> + '_ {
+        self.slots.iter().filter(|slot| slot.texture.is_some()).map(|slot| &slot.name)
+    }
+    // Replaces the texture of a name in place, or gives the name a new slot.
+    pub(crate) fn insert(&mut self, name: &str, texture: T) -> TextureHandle {
+        if let Some(handle) = self.handle(name) {
+            self.slots[handle.index as usize].texture = Some(texture);
+            return handle;
+        }
+        let slot = Slot { name: name.to_string(), texture: Some(texture), generation: 0, refs: 0 };
+        let index = match self.free.pop() {
+            Some(index) => {
+                let generation = self.slots[index as usize].generation;
+                self.slots[index as usize] = Slot { generation, ..slot };
+                index
+            }
+            None => {
+                self.slots.push(slot);
+                self.slots.len() as u32 - 1
+            }
+        };
+        self.names.insert(name.to_string(), index);
+        TextureHandle { index, generation: self.slots[index as usize].generation }
+    }
+    // Drops the texture but keeps the handle.
+    pub(crate) fn forget(&mut self, name: &str) -> bool {
+        match self.names.get(name) {
+            Some(index) => self.slots[*index as usize].texture.take().is_some(),
+            None => false,
+        }
+    }
+    pub(crate) fn forget_all(&mut self) {
+        for slot in &mut self.slots {
+            slot.texture = None;
+        }
+    }
+    // Frees the slot, the handle goes stale. Returns the name it had.
+    pub(crate) fn remove(&mut self, handle: TextureHandle) -> Option<String> {
+        let slot = self.slot_mut(handle)?;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.texture = None;
+        slot.refs = 0;
+        let name = std::mem::take(&mut slot.name);
+        self.names.remove(&name);
+        self.free.push(handle.index);
+        Some(name)
+    }
+    pub(crate) fn acquire(&mut self, handle: TextureHandle) {
+        if let Some(slot) = self.slot_mut(handle) {
+            slot.refs += 1;
+        }
+    }
+    // Returns how many references are left, None for stale handles.
+    pub(crate) fn release(&mut self, handle: TextureHandle) -> Option<u32> {
+        let slot = self.slot_mut(handle)?;
+        slot.refs = slot.refs.saturating_sub(1);
+        Some(slot.refs)
+    }
+}
+
+// The bytes of every mip level of a texture, made off the render thread by the background loader.
+#[derive(Debug)]
+pub(crate) struct TextureData {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) options: TextureOptions,
+    pub(crate) levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+    pub(crate) fn new(img: &image::DynamicImage, options: &TextureOptions) -> Self {
+        let dimensions = img.dimensions();
+        let float_source = matches!(img.color(), image::ColorType::Rgb32F | image::ColorType::Rgba32F);
+        // each level half the size of the one before, down to 1x1
+        let mip_level_count = if options.mipmaps {32 - dimensions.0.max(dimensions.1).max(1).leading_zeros()} else {1};
+        // linear copy of the last level, smaller levels are averaged in linear space
+        let mut level: Option<image::Rgba32FImage> = None;
+        let mut levels = vec![];
+        for mip_level in 0..mip_level_count {
+            let (w,h) = ((dimensions.0 >> mip_level).max(1),(dimensions.1 >> mip_level).max(1));
+            let bytes = if mip_level == 0 && options.format == TextureFormat::Srgb8 && !float_source {
+                img.to_rgba8().into_raw()
+            } else {
+                let source = level.take().unwrap_or_else(|| linear_rgba(img,float_source));
+                let next = if source.dimensions() == (w,h) {
+                    source
+                } else {
+                    image::imageops::resize(&source,w,h,image::imageops::FilterType::Triangle)
+                };
+                let bytes = texel_bytes(&next,options.format);
+                level = Some(next);
+                bytes
+            };
+            levels.push(bytes);
+        }
+        Self { width: dimensions.0, height: dimensions.1, options: *options, levels }
+    }
+}
+
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub bind_group: wgpu::BindGroup,
@@ -22,9 +173,10 @@ impl Texture {
         bytes: &[u8],
         label: &str,
         bind_group_layout: &wgpu::BindGroupLayout,
+        options: &TextureOptions,
     ) -> Result<Self> {
         let img = image::load_from_memory(bytes)?;
-        Self::from_image(device, queue, &img, Some(label), &bind_group_layout)
+        Self::from_image(device, queue, &img, Some(label), &bind_group_layout, options)
     }
 
     pub fn from_image(
@@ -33,55 +185,74 @@ impl Texture {
         img: &image::DynamicImage,
         label: Option<&str>,
         bind_group_layout: &wgpu::BindGroupLayout,
+        options: &TextureOptions,
     ) -> Result<Self> {
-        let rgba = img.to_rgba32f();
-        let dimensions = img.dimensions();
+        Self::from_data(device, queue, &TextureData::new(img, options), label, bind_group_layout)
+    }
 
+    // Only creates and uploads, the pixels are already in the texture's format.
+    pub(crate) fn from_data(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &TextureData,
+        label: Option<&str>,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Self> {
+        let options = &data.options;
+        let mip_level_count = data.levels.len() as u32;
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width: data.width,
+            height: data.height,
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(
             &wgpu::TextureDescriptor {
                 label,
                 size,
-                mip_level_count: 1,
+                mip_level_count,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba32Float,
+                format: options.format.wgpu(),
                 usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             }
         );
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba.as_bytes(),
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: std::num::NonZeroU32::new(16 * dimensions.0),
-                rows_per_image: std::num::NonZeroU32::new(dimensions.1),
-            },
-            size,
-        );
+        for (mip_level,bytes) in data.levels.iter().enumerate() {
+            let mip_level = mip_level as u32;
+            let (w,h) = ((data.width >> mip_level).max(1),(data.height >> mip_level).max(1));
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                bytes,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(options.format.bytes_per_pixel() * w),
+                    rows_per_image: std::num::NonZeroU32::new(h),
+                },
+                wgpu::Extent3d {
+                    width: w,
+                    height: h,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(
             &wgpu::SamplerDescriptor {
                 label,
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Nearest,
-                min_filter: wgpu::FilterMode::Nearest,
-                mipmap_filter: wgpu::FilterMode::Nearest,
+                address_mode_u: options.wrap.address_mode(),
+                address_mode_v: options.wrap.address_mode(),
+                address_mode_w: options.wrap.address_mode(),
+                mag_filter: options.filter.mode(),
+                min_filter: options.filter.mode(),
+                mipmap_filter: options.filter.mode(),
 
                 lod_min_clamp: 0.0,
-                lod_max_clamp: 0.0,
+                lod_max_clamp: (mip_level_count - 1) as f32,
                 compare: None,
                 anisotropy_clamp: None,
                 border_color: None
@@ -105,4 +276,194 @@ impl Texture {
 
         Ok(Self {texture, bind_group})
     }
-}
\ No newline at end of file
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+// Float pixels with linear color, alpha is linear either way.
+fn linear_rgba(img: &image::DynamicImage, float_source: bool) -> image::Rgba32FImage {
+    let mut rgba = img.to_rgba32f();
+    if !float_source {
+        for pixel in rgba.pixels_mut() {
+            for c in &mut pixel.0[..3] {
+                *c = srgb_to_linear(*c);
+            }
+        }
+    }
+    rgba
+}
+
+// Rounds to nearest, overflow goes to infinity and values too small for a half to zero.
+fn f16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if (bits >> 23) & 0xff == 0xff {
+        return sign | if mantissa == 0 { 0x7c00 } else { 0x7e00 };
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        return sign | ((mantissa | 0x80_0000) >> (14 - exponent)) as u16;
+    }
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    // a carry out of the mantissa correctly bumps the exponent
+    sign | (half + ((mantissa >> 12) & 1)) as u16
+}
+
+// Linear pixels as the bytes of a texture format.
+fn texel_bytes(rgba: &image::Rgba32FImage, format: TextureFormat) -> Vec<u8> {
+    match format {
+        TextureFormat::Srgb8 => rgba.pixels()
+            .flat_map(|p| {
+                let encode = |c: f32| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+                [encode(p.0[0]), encode(p.0[1]), encode(p.0[2]), (p.0[3].clamp(0.0, 1.0) * 255.0).round() as u8]
+            })
+            .collect(),
+        TextureFormat::Float16 => rgba.as_raw().iter()
+            .flat_map(|c| f16_bits(*c).to_le_bytes())
+            .collect(),
+        TextureFormat::Float32 => rgba.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn black_and_white() -> image::DynamicImage {
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(2,1,|x,_| {
+            if x == 0 { image::Rgba([0,0,0,255]) } else { image::Rgba([255,255,255,255]) }
+        }))
+    }
+
+    #[test]
+    fn mip_levels_halve_down_to_one_pixel() {
+        let img = image::DynamicImage::new_rgba8(8,2);
+        let data = TextureData::new(&img,&TextureOptions::default().mipmaps(true));
+        let sizes: Vec<usize> = data.levels.iter().map(|l| l.len() / 4).collect();
+        assert_eq!(sizes, vec![16,4,2,1]);
+        let data = TextureData::new(&img,&TextureOptions::default());
+        assert_eq!(data.levels.len(), 1);
+        assert_eq!((data.width,data.height), (8,2));
+    }
+
+    #[test]
+    fn first_level_keeps_the_pixels() {
+        let img = black_and_white();
+        let data = TextureData::new(&img,&TextureOptions::smooth());
+        assert_eq!(data.levels[0], img.to_rgba8().into_raw());
+    }
+
+    #[test]
+    fn mips_average_in_linear_space() {
+        let data = TextureData::new(&black_and_white(),&TextureOptions::smooth());
+        let pixel = &data.levels[1];
+        // half way in light is brighter than half way in sRGB bytes
+        assert!((187..=189).contains(&pixel[0]), "{:?}", pixel);
+        assert_eq!(pixel[0], pixel[1]);
+        assert_eq!(pixel[3], 255);
+    }
+
+    #[test]
+    fn float_levels_are_linear() {
+        let data = TextureData::new(&black_and_white(),&TextureOptions::smooth().format(TextureFormat::Float32));
+        let texel = |level: &[u8], i: usize| f32::from_le_bytes(level[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!(data.levels[0].len(), 2 * 16);
+        assert_eq!((texel(&data.levels[0],0),texel(&data.levels[0],4)), (0.0,1.0));
+        assert!((texel(&data.levels[1],0) - 0.5).abs() < 0.01);
+        let data = TextureData::new(&black_and_white(),&TextureOptions::default().format(TextureFormat::Float16));
+        assert_eq!(data.levels[0].len(), 2 * 8);
+    }
+
+    #[test]
+    fn f16_bits_rounds_and_saturates() {
+        assert_eq!(f16_bits(0.0), 0x0000);
+        assert_eq!(f16_bits(-0.0), 0x8000);
+        assert_eq!(f16_bits(1.0), 0x3c00);
+        assert_eq!(f16_bits(-2.0), 0xc000);
+        assert_eq!(f16_bits(0.5), 0x3800);
+        assert_eq!(f16_bits(65504.0), 0x7bff);
+        // halfway between 1 and the next half rounds up
+        assert_eq!(f16_bits(1.0 + 1.5 / 1024.0), 0x3c02);
+        // rounding up out of the mantissa carries into the exponent
+        assert_eq!(f16_bits(2.0 - 1.0 / 4096.0), 0x4000);
+        assert_eq!(f16_bits(1.0e6), 0x7c00);
+        assert_eq!(f16_bits(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f16_bits(f32::NAN) & 0x7e00, 0x7e00);
+        // subnormals, and values too small for one
+        assert_eq!(f16_bits(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f16_bits(2.0f32.powi(-15)), 0x0200);
+        assert_eq!(f16_bits(1.0e-9), 0x0000);
+    }
+
+    #[test]
+    fn names_keep_their_handles() {
+        let mut store = TextureStore::default();
+        let a = store.insert("a.png", 1);
+        assert_eq!(store.insert("a.png", 2), a);
+        assert_eq!(store.get(a), Some(&2));
+        assert!(store.forget("a.png"));
+        assert!(!store.forget("a.png"));
+        assert_eq!(store.get(a), None);
+        assert_eq!(store.handle("a.png"), Some(a));
+        assert_eq!(store.name(a), Some("a.png"));
+        assert!(!store.is_loaded("a.png"));
+        assert_eq!(store.names().count(), 0);
+        assert_eq!(store.insert("a.png", 3), a);
+        assert!(store.is_loaded("a.png"));
+    }
+
+    #[test]
+    fn removed_handles_go_stale() {
+        let mut store = TextureStore::default();
+        let a = store.insert("a.png", 1);
+        assert_eq!(store.remove(a), Some("a.png".to_string()));
+        assert_eq!(store.remove(a), None);
+        assert_eq!(store.handle("a.png"), None);
+        // the slot is reused with a new generation, the old handle doesn't see the new texture
+        let b = store.insert("b.png", 2);
+        assert_eq!(b.index, a.index);
+        assert_ne!(b, a);
+        assert_eq!(store.get(a), None);
+        assert_eq!(store.name(a), None);
+        assert_eq!(store.get(b), Some(&2));
+        assert_eq!(store.release(a), None);
+    }
+
+    #[test]
+    fn references_count_down_to_zero() {
+        let mut store = TextureStore::default();
+        let a = store.insert("a.png", 1);
+        store.acquire(a);
+        store.acquire(a);
+        assert_eq!(store.release(a), Some(1));
+        assert_eq!(store.release(a), Some(0));
+        assert_eq!(store.release(a), Some(0));
+        // a reused slot starts without references
+        store.acquire(a);
+        store.remove(a);
+        let b = store.insert("b.png", 2);
+        assert_eq!(store.release(b), Some(0));
+    }
+
+    #[test]
+    fn forget_all_keeps_every_name() {
+        let mut store = TextureStore::default();
+        let handles: Vec<TextureHandle> = ["a", "b", "c"].iter().enumerate().map(|(i, name)| store.insert(name, i)).collect();
+        store.forget_all();
+        assert!(handles.iter().all(|h| store.get(*h).is_none()));
+        assert_eq!(["a", "b", "c"].map(|name| store.handle(name)), [Some(handles[0]), Some(handles[1]), Some(handles[2])]);
+    }
+}
@@ -0,0 +1,135 @@
+// 2d affine transform, points map as
+// x' = a * x + c * y + tx
+// y' = b * x + d * y + ty
+// Angles are radians, clockwise on screen since y points down.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self { a: 1.0, b: 0.0, c: 0.0, d: 1.0, tx: 0.0, ty: 0.0 };
+
+    pub fn translation(x: f32, y: f32) -> Self {
+        Self { tx: x, ty: y, ..Self::IDENTITY }
+    }
+    pub fn rotation(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self { a: cos, b: sin, c: -sin, d: cos, tx: 0.0, ty: 0.0 }
+    }
+    pub fn scaling(sx: f32, sy: f32) -> Self {
+        Self { a: sx, d: sy, ..Self::IDENTITY }
+    }
+    // Shear angles in radians along x and y.
+    pub fn skewing(kx: f32, ky: f32) -> Self {
+        Self { b: ky.tan(), c: kx.tan(), ..Self::IDENTITY }
+    }
+    // Applies other first, then self.
+    pub fn multiply(&self, other: &Transform) -> Self {
+        Self {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            tx: self.a * other.tx + self.c * other.ty + self.tx,
+            ty: self.b * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+    // Wraps other so it happens around the pivot instead of the origin.
+    pub fn around(other: &Transform, pivot: [f32; 2]) -> Self {
+        Self::translation(pivot[0], pivot[1])
+            .multiply(other)
+            .multiply(&Self::translation(-pivot[0], -pivot[1]))
+    }
+    pub fn apply(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            self.a * p[0] + self.c * p[1] + self.tx,
+            self.b * p[0] + self.d * p[1] + self.ty,
+        ]
+    }
+    // Columns of the 2x2 part, as the shader wants it.
+    pub fn linear(&self) -> [f32; 4] {
+        [self.a, self.b, self.c, self.d]
+    }
+    pub fn determinant(&self) -> f32 {
+        self.a * self.d - self.b * self.c
+    }
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Self {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn close(p: [f32; 2], q: [f32; 2]) -> bool {
+        (p[0] - q[0]).abs() < 1e-4 && (p[1] - q[1]).abs() < 1e-4
+    }
+
+    #[test]
+    fn rotation_is_clockwise_on_screen() {
+        assert!(close(Transform::rotation(FRAC_PI_2).apply([1.0,0.0]), [0.0,1.0]));
+    }
+
+    #[test]
+    fn multiply_applies_the_right_side_first() {
+        let t = Transform::translation(10.0,0.0).multiply(&Transform::scaling(2.0,3.0));
+        assert!(close(t.apply([1.0,1.0]), [12.0,3.0]));
+    }
+
+    #[test]
+    fn around_keeps_the_pivot_in_place() {
+        let pivot = [5.0,7.0];
+        let t = Transform::around(&Transform::rotation(1.0).multiply(&Transform::scaling(2.0,0.5)), pivot);
+        assert!(close(t.apply(pivot), pivot));
+        assert!(close(t.apply([6.0,7.0]), {
+            let (sin, cos) = 1.0f32.sin_cos();
+            [5.0 + 2.0 * cos, 7.0 + 2.0 * sin]
+        }));
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let t = Transform::translation(3.0,-4.0)
+            .multiply(&Transform::rotation(0.7))
+            .multiply(&Transform::skewing(0.3,-0.2))
+            .multiply(&Transform::scaling(2.0,-0.5));
+        let inverse = t.inverse().unwrap();
+        for p in [[0.0,0.0],[1.0,2.0],[-30.0,12.5]] {
+            assert!(close(inverse.apply(t.apply(p)), p));
+        }
+        let identity = t.multiply(&inverse);
+        assert!(close(identity.apply([9.0,-2.0]), [9.0,-2.0]));
+    }
+
+    #[test]
+    fn flat_transforms_have_no_inverse() {
+        assert_eq!(Transform::scaling(0.0,1.0).inverse(), None);
+        assert_eq!(Transform::scaling(2.0,0.0).determinant(), 0.0);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
+use crate::assets::Assets;
+use crate::paint::TextureOptions;
+use crate::texture::TextureData;
+
+// A watched file that changed on disk. Images come decoded with their mip levels made, so the
+// render thread only uploads them.
+pub(crate) struct Change {
+    pub(crate) path: String,
+    pub(crate) image: Option<TextureData>,
+}
+
+#[derive(Debug)]
+struct Watched {
+    modified: Option<SystemTime>,
+    // how to make the texture of an image, None for other files
+    image: Option<TextureOptions>,
+    // the change that last failed to decode, so it's only reported once
+    failed: Option<SystemTime>,
+}
+
+// Polls the modification times of files on a thread of its own, which stops when the watcher is dropped.
+#[derive(Debug)]
+pub(crate) struct Watcher {
+    files: Arc<Mutex<HashMap<String, Watched>>>,
+    changes: mpsc::Receiver<Change>,
+    assets: Assets,
+}
+
+impl Watcher {
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    // Only files on disk are watched, packed and embedded files never change.
+    pub(crate) fn new(assets: Assets) -> Self {
+        let files = Arc::new(Mutex::new(HashMap::new()));
+        let (sender, changes) = mpsc::channel();
+        let weak = Arc::downgrade(&files);
+        std::thread::Builder::new()
+            .name("granseal file watcher".to_string())
+            .spawn({
+                let assets = assets.clone();
+                move || Watcher::poll(weak, sender, assets)
+            })
+            .expect("Couldn't start the file watcher.");
+        Self { files, changes, assets }
+    }
+    fn poll(files: Weak<Mutex<HashMap<String, Watched>>>, sender: mpsc::Sender<Change>, assets: Assets) {
+        loop {
+            std::thread::sleep(Watcher::INTERVAL);
+            let files = match files.upgrade() {
+                Some(files) => files,
+                None => return,
+            };
+            // the lock isn't held while touching the disk
+            let watched: Vec<(String, Option<SystemTime>, Option<TextureOptions>)> = files.lock().unwrap().iter()
+                .map(|(path, w)| (path.clone(), w.modified, w.image))
+                .collect();
+            for (path, before, image) in watched {
+                let now = assets.modified(&path);
+                if now.is_none() || now == before {
+                    continue;
+                }
+                let image = if let Some(options) = image {
+                    match assets.open_image(&path) {
+                        Ok(image) => Some(TextureData::new(&image, &options)),
+                        // likely caught halfway through being written, it's tried again next time
+                        Err(e) => {
+                            if let Some(w) = files.lock().unwrap().get_mut(&path) {
+                                if w.failed != now {
+                                    println!("Unable to reload image: {} -> {}", path, e);
+                                    w.failed = now;
+                                }
+                            }
+                            continue;
+                        }
+                    }
+                } else {
+                    None
+                };
+                if let Some(w) = files.lock().unwrap().get_mut(&path) {
+                    w.modified = now;
+                }
+                if sender.send(Change { path, image }).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    // Files that don't exist yet are reported once they appear. Watching an image again updates
+    // its options.
+    pub(crate) fn watch(&self, path: &str, image: Option<TextureOptions>) {
+        self.files.lock().unwrap()
+            .entry(path.to_string())
+            .and_modify(|w| if image.is_some() { w.image = image })
+            .or_insert_with(|| Watched { modified: self.assets.modified(path), image, failed: None });
+    }
+    pub(crate) fn unwatch(&self, path: &str) {
+        self.files.lock().unwrap().remove(path);
+    }
+    pub(crate) fn changes(&self) -> Vec<Change> {
+        self.changes.try_iter().collect()
+    }
+}
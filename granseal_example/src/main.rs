@@ -10,7 +10,13 @@ use rand::prelude::*;
 use rand_xorshift::XorShiftRng;
 
 use granseal_engine::{events::{Event, Key}, GransealGameConfig, GransealGameState, shape::*, VSyncMode};
-use granseal_engine::events::KeyState;
+use granseal_engine::events::{KeyState, MouseButton};
+use granseal_engine::path::Path;
+use granseal_engine::paint::{Dash, Gradient, Shadow, TextureOptions, Tiling};
+use granseal_engine::kinds::ShapeFunction;
+use granseal_engine::scene::{Drawable, Node, NodeId, Scene};
+use granseal_engine::particles::Emitter;
+use granseal_engine::sprites::{Animation, AnimationEvent, AnimationPlayer, Grid, Playback, SpriteData, SpriteSheet};
 use granseal_engine::renderer::{Castle};
 
 pub struct Vector2d {
@@ -51,7 +57,7 @@ impl Entity {
             angle: r.gen_range(0.0..6.28),
             a_vel: r.gen_range(-6.0..6.0),
             image: None,
-            kind: r.gen_range(0..=4),
+            kind: *[ShapeKind::FillRect,ShapeKind::FillOval,ShapeKind::Rect,ShapeKind::Oval,ShapeKind::TexRect].choose(&mut r).unwrap(),
             thickness: r.gen_range(2.0..16.0)
         }
     }
@@ -64,7 +70,7 @@ impl Entity {
             angle: 0.0,
             a_vel: 0.0,
             image: None,
-            kind: FILL_RECT,
+            kind: ShapeKind::FillRect,
             thickness: 2.0,
         }
     }
@@ -90,7 +96,7 @@ impl Entity {
     }
     fn image(mut self, img: String) -> Self {
         self.image = Some(img);
-        self.kind = TEX_RECT;
+        self.kind = ShapeKind::TexRect;
         self
     }
 }
@@ -108,6 +114,20 @@ pub struct GameState {
     rotate: bool,
     init: bool,
     clear_cache: bool,
+    diamond: Option<ShapeKind>,
+    scene: Scene,
+    blades: NodeId,
+    fountain: Emitter,
+    sparks: Emitter,
+    tiles: SpriteSheet,
+    tile_player: AnimationPlayer,
+    spinner: SpriteData,
+    spinner_player: AnimationPlayer,
+    blob: Option<TextureHandle>,
+    canvas: Option<TextureHandle>,
+    // held for as long as the game runs, the tree until 5 releases it
+    images: Vec<TextureHandle>,
+    tree: Option<TextureHandle>,
 }
 
 impl GameState {
@@ -122,35 +142,35 @@ impl GameState {
         let w = width as f32;
         let h = height as f32;
         let mut test = vec!(
-            Entity::new(0.0,0.0).size(64.0,64.0).color(Color::NAVY).kind(FILL_RECT),
-            Entity::new(w - 64.0,0.0).size(64.0,64.0).color(Color::NAVY).kind(FILL_RECT),
-            Entity::new(w - 64.0,h - 64.0).size(64.0,64.0).color(Color::NAVY).kind(FILL_RECT),
-            Entity::new( 0.0, h - 64.0).size(64.0,64.0).color(Color::NAVY).kind(FILL_RECT),
+            Entity::new(0.0,0.0).size(64.0,64.0).color(Color::NAVY).kind(ShapeKind::FillRect),
+            Entity::new(w - 64.0,0.0).size(64.0,64.0).color(Color::NAVY).kind(ShapeKind::FillRect),
+            Entity::new(w - 64.0,h - 64.0).size(64.0,64.0).color(Color::NAVY).kind(ShapeKind::FillRect),
+            Entity::new( 0.0, h - 64.0).size(64.0,64.0).color(Color::NAVY).kind(ShapeKind::FillRect),
 
-            Entity::new(64.0,64.0).size(64.0,64.0).color(Color::CYAN).kind(RECT),
-            Entity::new(w - 64.0 - 64.0,64.0).size(64.0,64.0).color(Color::CYAN).kind(RECT),
-            Entity::new(w - 64.0 - 64.0,h - 64.0 - 64.0).size(64.0,64.0).color(Color::CYAN).kind(RECT),
-            Entity::new( 64.0, h - 64.0 - 64.0).size(64.0,64.0).color(Color::CYAN).kind(RECT),
+            Entity::new(64.0,64.0).size(64.0,64.0).color(Color::CYAN).kind(ShapeKind::Rect),
+            Entity::new(w - 64.0 - 64.0,64.0).size(64.0,64.0).color(Color::CYAN).kind(ShapeKind::Rect),
+            Entity::new(w - 64.0 - 64.0,h - 64.0 - 64.0).size(64.0,64.0).color(Color::CYAN).kind(ShapeKind::Rect),
+            Entity::new( 64.0, h - 64.0 - 64.0).size(64.0,64.0).color(Color::CYAN).kind(ShapeKind::Rect),
 
-            Entity::new(0.0,64.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_RECT).image(String::from("blob.png")),
-            Entity::new(w - 64.0,64.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_RECT),
-            Entity::new(w - 64.0,h - 128.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_RECT),
-            Entity::new( 0.0, h - 128.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_RECT),
+            Entity::new(0.0,64.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexRect).image(String::from("blob.png")),
+            Entity::new(w - 64.0,64.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexRect),
+            Entity::new(w - 64.0,h - 128.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexRect),
+            Entity::new( 0.0, h - 128.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexRect),
 
-            Entity::new(64.0,128.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_OVAL),
-            Entity::new(w - 64.0 - 64.0,128.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_OVAL),
-            Entity::new(w - 64.0 - 64.0,h - 128.0 - 64.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_OVAL),
-            Entity::new( 64.0, h - 64.0 - 128.0).size(64.0,64.0).color(Color::WHITE).kind(TEX_OVAL),
+            Entity::new(64.0,128.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexOval),
+            Entity::new(w - 64.0 - 64.0,128.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexOval),
+            Entity::new(w - 64.0 - 64.0,h - 128.0 - 64.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexOval),
+            Entity::new( 64.0, h - 64.0 - 128.0).size(64.0,64.0).color(Color::WHITE).kind(ShapeKind::TexOval),
 
-            Entity::new(128.0,128.0).size(64.0,64.0).color(Color::MAGENTA).kind(OVAL),
-            Entity::new(w - 128.0 - 64.0,128.0).size(64.0,64.0).color(Color::MAGENTA).kind(OVAL),
-            Entity::new(w - 128.0 - 64.0,h - 128.0 - 64.0).size(64.0,64.0).color(Color::MAGENTA).kind(OVAL),
-            Entity::new( 128.0, h - 128.0 - 64.0).size(64.0,64.0).color(Color::MAGENTA).kind(OVAL),
+            Entity::new(128.0,128.0).size(64.0,64.0).color(Color::MAGENTA).kind(ShapeKind::Oval),
+            Entity::new(w - 128.0 - 64.0,128.0).size(64.0,64.0).color(Color::MAGENTA).kind(ShapeKind::Oval),
+            Entity::new(w - 128.0 - 64.0,h - 128.0 - 64.0).size(64.0,64.0).color(Color::MAGENTA).kind(ShapeKind::Oval),
+            Entity::new( 128.0, h - 128.0 - 64.0).size(64.0,64.0).color(Color::MAGENTA).kind(ShapeKind::Oval),
 
-            Entity::new(192.0,192.0).size(64.0,64.0).color(Color::RED).kind(FILL_OVAL),
-            Entity::new(w - 256.0,192.0).size(64.0,64.0).color(Color::RED).kind(FILL_OVAL),
-            Entity::new(w - 256.0,h - 256.0).size(64.0,64.0).color(Color::RED).kind(FILL_OVAL),
-            Entity::new( 192.0, h - 256.0).size(64.0,64.0).color(Color::RED).kind(FILL_OVAL),
+            Entity::new(192.0,192.0).size(64.0,64.0).color(Color::RED).kind(ShapeKind::FillOval),
+            Entity::new(w - 256.0,192.0).size(64.0,64.0).color(Color::RED).kind(ShapeKind::FillOval),
+            Entity::new(w - 256.0,h - 256.0).size(64.0,64.0).color(Color::RED).kind(ShapeKind::FillOval),
+            Entity::new( 192.0, h - 256.0).size(64.0,64.0).color(Color::RED).kind(ShapeKind::FillOval),
         );
 
         entities.append(&mut test);
@@ -167,13 +187,30 @@ impl GameState {
         //             color: Color::rgb(r.gen(),r.gen(),r.gen()),
         //             angle: 0.0,
         //             a_vel: r.gen_range(-6.0..6.0),
-        //             kind: TEX_OVAL
+        //             kind: ShapeKind::TexOval
         //         })
         //     }
         // }
 
         entities.iter_mut().for_each(|f|{f.a_vel= r.gen_range(-6.0..6.0)});
         println!("Entities: {:?}",entities.len());
+
+        // a windmill, the blades turn with their hub and the sails with their blades
+        let mut scene = Scene::new();
+        let mill = scene.add(Node::new(Drawable::FillRect { width: 24.0, height: 96.0 }).at(900.0,560.0).color(Color::MAROON));
+        let blades = scene.add_child(mill,Node::group().at(12.0,0.0).z(1)).unwrap();
+        for i in 0..4 {
+            let blade = scene.add_child(blades,Node::new(Drawable::FillRect { width: 64.0, height: 6.0 })
+                .at(0.0,-3.0)
+                .origin(0.0,3.0)
+                .rotation(i as f32 * std::f32::consts::FRAC_PI_2)
+                .color(Color::SILVER)).unwrap();
+            scene.add_child(blade,Node::new(Drawable::FillRoundRect { width: 40.0, height: 16.0, radius: 4.0 })
+                .at(22.0,6.0)
+                .color(Color::WHITE)
+                .outline(Some(Color::GRAY))).unwrap();
+        }
+        scene.add_child(blades,Node::new(Drawable::FillOval { width: 12.0, height: 12.0 }).at(-6.0,-6.0).z(1).color(Color::YELLOW));
         Self {
             width,
             height,
@@ -190,6 +227,33 @@ impl GameState {
             rotate: false,
             init: false,
             clear_cache: false,
+            diamond: None,
+            scene,
+            blades,
+            fountain: Emitter::new(700.0,740.0)
+                .rate(120.0)
+                .lifetime(1.0,1.8)
+                .speed(220.0,300.0)
+                .direction(-std::f32::consts::FRAC_PI_2,0.4)
+                .gravity(0.0,400.0)
+                .colors(Color::CYAN,Color::new(0.0,0.0,1.0,0.0))
+                .sizes(6.0,2.0),
+            sparks: Emitter::new(0.0,0.0)
+                .rate(0.0)
+                .lifetime(0.3,0.8)
+                .speed(40.0,240.0)
+                .spin(-8.0,8.0)
+                .colors(Color::YELLOW,Color::new(1.0,0.0,0.0,0.0))
+                .sizes(24.0,4.0)
+                .image("token.png"),
+            tiles: SpriteSheet::default(),
+            tile_player: AnimationPlayer::new(Animation::range(0..16,0.15,Playback::PingPong)),
+            spinner: SpriteData::default(),
+            spinner_player: AnimationPlayer::new(Animation::new(Playback::Loop)),
+            blob: None,
+            canvas: None,
+            images: vec![],
+            tree: None,
         }
     }
     fn update(&mut self,delta: &Duration, castle: &mut Castle) {
@@ -213,6 +277,16 @@ impl GameState {
             if e.pos.x >= self.width as f32 - e.size.x {e.velocity.x *= -1.0}
             if e.pos.y >= self.height as f32 - e.size.y {e.velocity.y *= -1.0}
         }
+        self.fountain.update(delta);
+        self.sparks.update(delta);
+        self.spinner_player.update(delta);
+        for event in self.tile_player.update(delta) {
+            // a little flourish every time the tiles come back around
+            if event == AnimationEvent::Looped {
+                self.sparks.position = [904.0,560.0];
+                self.sparks.burst(10);
+            }
+        }
     }
     fn render(&mut self, g: &mut Graphics) {
         if self.clear_cache {
@@ -220,31 +294,144 @@ impl GameState {
             g.clear_texture_cache();
         }
         g.clear();
-        g.set_translation(self.position.x,self.position.y);
-        g.image("blob.png",0.0,0.0);
+        g.translate(self.position.x,self.position.y);
+        if let Some(blob) = self.blob {
+            g.image(blob,0.0,0.0);
+        }
         g.image("happy-tree.png",500.0,500.0);
         g.image("token.png", 200.0,0.0);
-        g.image("happy-tree-alpha.png",500.0,200.0);
+        if let Some(tree) = self.tree {
+            g.image(tree,500.0,200.0);
+        }
         let r = &mut self.rng;
-        for e in &mut self.entities {
+        for (i, e) in self.entities.iter_mut().enumerate() {
+            g.tag(Some(i as u64));
             if self.flash {e.color = Color::new(r.gen(),r.gen(),r.gen(),r.gen());}
             g.outline_thickness(e.thickness);
             g.color(Color::new(e.color.r,e.color.g,e.color.b,0.1));
-            g.set_rotation(e.angle);
+            g.push_transform();
+            g.set_origin(e.pos.x + e.size.x / 2.0,e.pos.y + e.size.y / 2.0);
+            g.rotate(e.angle);
             g.outline_color(Color::invert(e.color));
             match e.kind {
-                TEX_RECT => {g.image("blob.png",e.pos.x,e.pos.y);}
-                RECT => {g.rect(e.pos.x,e.pos.y,e.size.x,e.size.y);},
-                OVAL => {g.oval(e.pos.x,e.pos.y,e.size.x,e.size.y);},
-                FILL_RECT => {g.fill_rect(e.pos.x,e.pos.y,e.size.x,e.size.y);},
-                FILL_OVAL => {g.fill_oval(e.pos.x,e.pos.y,e.size.x,e.size.y);},
+                ShapeKind::TexRect => {g.image("blob.png",e.pos.x,e.pos.y);}
+                ShapeKind::Rect => {g.rect(e.pos.x,e.pos.y,e.size.x,e.size.y);},
+                ShapeKind::Oval => {g.oval(e.pos.x,e.pos.y,e.size.x,e.size.y);},
+                ShapeKind::FillRect => {g.fill_rect(e.pos.x,e.pos.y,e.size.x,e.size.y);},
+                ShapeKind::FillOval => {g.fill_oval(e.pos.x,e.pos.y,e.size.x,e.size.y);},
                 _ => {;}
             }
+            g.pop_transform();
+        }
+        g.tag(None);
+        let t = self.timer.elapsed().as_secs_f32();
+        // a group of shapes orbiting a common point, squashed by a scale
+        g.push_transform();
+        g.translate(860.0,120.0);
+        g.scale(1.0,0.6);
+        g.rotate(t);
+        g.color(Color::YELLOW);
+        g.fill_oval(-16.0,-16.0,32.0,32.0);
+        g.color(Color::BLUE);
+        g.fill_rect(60.0,-8.0,16.0,16.0);
+        g.translate(68.0,0.0);
+        g.rotate(t * 3.0);
+        g.color(Color::SILVER);
+        g.fill_oval(20.0,-4.0,8.0,8.0);
+        g.pop_transform();
+        g.color(Color::LIME);
+        g.fill_pie(300.0,300.0,64.0,64.0,0.0,t % std::f32::consts::TAU);
+        g.color(Color::YELLOW);
+        g.fill_ring(380.0,300.0,64.0,64.0,0.6,-std::f32::consts::FRAC_PI_2,t % std::f32::consts::TAU - std::f32::consts::FRAC_PI_2);
+        g.outline_thickness(4.0);
+        g.color(Color::CYAN);
+        g.arc(460.0,300.0,64.0,32.0,t,t + std::f32::consts::PI);
+        g.gradient(Gradient::linear(t).stop(0.0,Color::RED).stop(0.5,Color::YELLOW).stop(1.0,Color::BLUE));
+        g.fill_rect(540.0,300.0,96.0,64.0);
+        g.gradient(Gradient::radial(0.35,0.35,0.6).stop(0.0,Color::WHITE).stop(1.0,Color::NAVY));
+        g.fill_oval(660.0,300.0,64.0,64.0);
+        g.fill_polygon(&[[760.0,300.0],[790.0,360.0],[740.0,330.0],[800.0,330.0],[750.0,360.0]]);
+        g.shadow(Some(Shadow::new(6.0,6.0,12.0)));
+        g.color(Color::SILVER);
+        g.fill_round_rect(40.0,300.0,200.0,80.0,16.0);
+        g.shadow(Some(Shadow::glow(16.0,Color::CYAN).spread(2.0)));
+        g.color(Color::TEAL);
+        g.fill_oval(250.0,420.0,48.0,48.0);
+        g.shadow(None);
+        g.color(Color::WHITE);
+        g.outline_thickness(2.0);
+        g.outline_dash(Some(Dash::new(8.0,4.0).offset(t * 16.0)));
+        g.rect(290.0,290.0,520.0,84.0);
+        g.outline_dash(Some(Dash::dotted(8.0)));
+        g.oval(40.0,420.0,200.0,120.0);
+        g.outline_dash(None);
+        g.outline_thickness(3.0);
+        g.stroke_path(&Path::new()
+            .move_to(300.0,400.0)
+            .cubic_to(350.0,300.0 + t.sin() * 100.0,450.0,500.0,500.0,400.0)
+            .quad_to(550.0,350.0,600.0,400.0)
+            .line_to(600.0,450.0)
+            .close());
+        if let Some(diamond) = self.diamond {
+            g.color(Color::LIME);
+            g.shape(diamond,860.0,400.0,48.0,72.0);
+        }
+        if let Some(blades) = self.scene.get_mut(self.blades) {
+            blades.rotation = t;
+        }
+        self.scene.draw(g);
+        // a flag waving in the wind, a strip of vertices bent along a sine
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for i in 0..=16 {
+            let u = i as f32 / 16.0;
+            let wave = (u * 6.0 - t * 4.0).sin() * 8.0 * u;
+            let shade = Color::rgb(1.0 - u * 0.4, 1.0 - u * 0.4, 1.0);
+            vertices.push(Vertex::new(860.0 + u * 128.0, 200.0 + wave, u, 0.0, shade));
+            vertices.push(Vertex::new(860.0 + u * 128.0, 280.0 + wave, u, 1.0, shade));
+            if i > 0 {
+                let v = i * 2;
+                indices.extend_from_slice(&[v - 2, v - 1, v, v - 1, v + 1, v]);
+            }
+        }
+        if let Some(blob) = self.blob {
+            if let Err(e) = g.mesh(&vertices,&indices,Some(&blob)) {
+                println!("{}",e);
+            }
+        }
+        self.fountain.draw(g);
+        self.sparks.draw(g);
+        // a scrolling, mirrored floor
+        g.tile_image("token.png",40.0,672.0,480.0,64.0,Tiling::mirror().scale(0.16,0.16).offset(t * 32.0,0.0));
+        // the top left quarter of the tree
+        g.image_region("happy-tree.png",Rect::new(0.0,0.0,128.0,128.0),Rect::new(760.0,400.0,96.0,96.0));
+        // the tree cut into 16 frames, played back and forth
+        g.sprite(&self.tiles,self.tile_player.frame(),872.0,528.0);
+        // quarters of the tree from an Aseprite export, drawn around their pivot
+        g.sprite(&self.spinner.sheet,self.spinner_player.frame(),960.0,660.0);
+        // the cover shrunk down, smooth thanks to mipmaps
+        g.push_transform();
+        g.translate(300.0,560.0);
+        g.scale(0.12 + t.sin().abs() * 0.08,0.12 + t.sin().abs() * 0.08);
+        g.image("cover.png",0.0,0.0);
+        g.pop_transform();
+        // a brush tracing a figure eight, only the few pixels under it go up to the gpu each frame
+        if let Some(canvas) = self.canvas {
+            if let Some(pixels) = g.pixels_mut(canvas) {
+                let (x,y) = (31.0 + (t * 0.7).sin() * 28.0,31.0 + (t * 1.4).sin() * 28.0);
+                pixels.fill_rect(x as u32,y as u32,2,2,Color::rgb(self.rng.gen(),self.rng.gen(),self.rng.gen()));
+            }
+            g.image(canvas,320.0,640.0);
         }
+        // a panel that stretches without smearing its corners
+        g.color(Color::WHITE);
+        g.nine_slice("blob.png",40.0,560.0,240.0 + t.sin() * 80.0,96.0,Insets::uniform(32.0));
         g.color(Color::WHITE);
-        g.rotate(self.timer.elapsed().as_secs_f32());
+        g.push_transform();
+        g.set_origin(110.0 + 64.0,220.0 + 64.0);
+        g.rotate(t);
         g.image("blob.png",110.0,220.0);
-        g.set_rotation(0.0);
+        g.pop_transform();
     }
 
 }
@@ -267,6 +454,16 @@ impl GransealGameState for GameState {
                 key: Key::Key3,
                 ..
             } => {self.rotate = !self.rotate}
+            // the texture is freed with its last reference, and the handle goes stale
+            Event::KeyEvent {
+                state: KeyState::Pressed,
+                key: Key::Key5,
+                ..
+            } => {
+                if let Some(tree) = self.tree.take() {
+                    g.release(tree);
+                }
+            }
             Event::KeyEvent {
                 state,
                 key: Key::Key4,
@@ -280,8 +477,66 @@ impl GransealGameState for GameState {
                 key: Key::F5,
                 ..
             } => {self.clear_cache = true}
+            // clicking an entity inverts its color
+            Event::MouseButton {
+                state: KeyState::Pressed,
+                button: MouseButton::Left,
+                position,
+                ..
+            } => {
+                if let Some(i) = g.hit_tag(position[0] as f32,position[1] as f32) {
+                    let e = &mut self.entities[i as usize];
+                    e.color = Color::invert(e.color);
+                }
+            }
+            // right clicking throws sparks
+            Event::MouseButton {
+                state: KeyState::Pressed,
+                button: MouseButton::Right,
+                position,
+                ..
+            } => {
+                self.sparks.position = [position[0] as f32,position[1] as f32];
+                self.sparks.burst(40);
+            }
             Event::MouseButton { .. } => {}
             Event::MouseMoved { .. } => {}
+            // saving an image shows up on its own, sprite data is read again here
+            Event::Reloaded(path) if path == "happy-tree.json" => {
+                if let Some(spinner) = g.load_sprite_data(path) {
+                    self.spinner = spinner;
+                }
+            }
+            Event::Load => {
+                // the images sit at the top of the repository, three up from target/debug
+                g.assets().search_path("../../..");
+                g.hot_reload(true);
+                // the images share an atlas page so drawing them doesn't switch textures
+                self.blob = g.load("blob.png");
+                self.canvas = Some(g.create_texture("noise",64,64));
+                self.images = ["happy-tree.png","token.png"].into_iter()
+                    .filter_map(|img| g.load(img))
+                    .collect();
+                self.tree = g.load("happy-tree-alpha.png");
+                g.pack_atlas();
+                g.set_texture_options("cover.png",TextureOptions::smooth());
+                // the cover isn't loaded yet, it decodes on a worker the first time it's drawn
+                g.background_loading(true);
+                self.tiles = g.load_sprite_sheet("happy-tree.png",Grid::new(64.0,64.0));
+                if let Some(spinner) = g.load_sprite_data("happy-tree.json") {
+                    if let Some(spin) = spinner.animations.get("spin") {
+                        self.spinner_player.play(spin);
+                    }
+                    self.spinner = spinner;
+                }
+                let diamond = g.register_shape("diamond",ShapeFunction::Fill(
+                    "    return (abs(p.x) / r.x + abs(p.y) / r.y - 1.0) * min(r.x, r.y) * 0.7071;".to_string()
+                ));
+                match diamond {
+                    Ok(kind) => self.diamond = Some(kind),
+                    Err(e) => println!("{}",e),
+                }
+            }
             Event::Draw => self.render(g),
             Event::Update(delta) => self.update(delta,c),
             _ => {}